@@ -1,8 +1,17 @@
-import bpy, struct, time, mathutils
+import bpy, struct, sys, time, mathutils
 from pathlib import Path
 from mathutils import Matrix, Euler
 
+# Writes animation records in the layout pack/src/animation.rs reads, big endian:
+# u32 record length, b'A', name, b'#', u8 compression, u16 joints, u32 frames,
+# then translation, euler rotation and scale of every joint of every frame as 3 f32 each, b'END'.
+# pack/tests/round_trip.rs builds a record the same way, change both together.
+
 res = bytearray()
+# arguments after "--": the output file, pack::MAX_JOINTS, then the fbx files to compile
+args = sys.argv[sys.argv.index("--") + 1:]
+# the most joints the game can animate
+MAX_JOINTS = int(args[1])
 
 def euler_mat(e: Euler):
     return mathutils.Matrix.Rotation(e.z, 4, 'Z') @ \
@@ -16,6 +25,9 @@ def write_str(v: any):  res.extend(str.encode(str(v))+b'#')
 def write_u8(v: any):
     if v > 255: raise Exception("Value is bigger than 255")
     res.extend(v.to_bytes(1, byteorder='big', signed=False))
+def write_u16(v: any):
+    if v > 65535: raise Exception("Value is bigger than 65535")
+    res.extend(v.to_bytes(2, byteorder='big', signed=False))
 def write_mat4x4(mat: any):
     res.extend(struct.pack(">f", mat[0][0])); res.extend(struct.pack(">f", mat[1][0]))
     res.extend(struct.pack(">f", mat[2][0])); res.extend(struct.pack(">f", mat[3][0]))
@@ -41,7 +53,9 @@ def set_last_frame():
 
 def export_frames():
     frames = bpy.context.scene.frame_end
-    write_u8(len(bpy.context.selected_pose_bones))
+    joints = len(bpy.context.selected_pose_bones)
+    if joints > MAX_JOINTS: raise Exception(f"Armature has {joints} joints, the game can not animate more than {MAX_JOINTS}")
+    write_u16(joints)
     write_u32(frames)
     for frame in range(frames):
         bpy.context.scene.frame_set(frame)
@@ -53,24 +67,24 @@ def export_frames():
             # write_mat4x4(bone.matrix @ (bone.parent.matrix.inverted_safe()) if bone.parent else bone.matrix)
 
 def export_animation(path: Path, start: time):
+    record_start = len(res)
+    write_u32(0)
     write_byte(b'A')
     write_str(path.name.split('.')[0])
+    write_u8(0) # not compressed, the compiler compresses records after reading them back
     bpy.ops.object.mode_set(mode='POSE')
     for obj in bpy.context.scene.objects: obj.select_set(True)
     set_last_frame()
     export_frames()
     write_bytes(b'END')
-    print(f"animation: ./{str(path)}, compiled in : {(time.time() - start):.2f} sec")
+    res[record_start:record_start + 4] = (len(res) - record_start - 4).to_bytes(4, byteorder='big', signed=False)
+    print(f"animation: {str(path)}, compiled in : {(time.time() - start):.2f} sec")
 
-for path in Path("./assets/animations/").glob("**/*.gltf"):
-    start = time.time()
-    clear_scene()
-    bpy.ops.import_scene.gltf(filepath=str(path))
-    export_animation(path, start)
-for path in Path("./assets/animations/").glob("**/*.fbx"):
+for path in map(Path, args[2:]):
     start = time.time()
     clear_scene()
     bpy.ops.import_scene.fbx(filepath=str(path))
     export_animation(path, start)
 
-open("./assets/compiled.bin", "ab").write(res)
\ No newline at end of file
+# records are read back by the compiler, which builds the pack
+open(args[0], "wb").write(res)
\ No newline at end of file
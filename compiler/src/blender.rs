@@ -1,16 +1,47 @@
 use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-pub fn run_blender_compiler() {
-    std::fs::File::open("./compiler/compile.py").expect("./compiler/compile.py not found.");
+/// Built into the compiler so it runs from any directory, editing it rebuilds the cached animations with the rest of the compiler
+const COMPILE_PY: &str = include_str!("../compile.py");
+
+/// Blender runs started by this process, names their temporary files apart
+static RUNS: AtomicUsize = AtomicUsize::new(0);
+
+/// Runs compile.py inside blender on the fbx files and returns the animation records it wrote.
+/// The script and its output are written to temporary files of this run only, other compiles can run at the same time
+pub fn run_blender_compiler(files: &[PathBuf]) -> Result<Vec<Vec<u8>>, String> {
+    let prefix = format!("nexodia_{}_{}", std::process::id(), RUNS.fetch_add(1, Ordering::SeqCst));
+    let script = std::env::temp_dir().join(format!("{}_compile.py", prefix));
+    let output = std::env::temp_dir().join(format!("{}_animations.bin", prefix));
+    let res = match std::fs::write(&script, COMPILE_PY) {
+        Ok(()) => run(files, &script, &output),
+        Err(e) => Err(format!("can not write {}, error: {}", script.display(), e))
+    };
+    std::fs::remove_file(&script).ok();
+    std::fs::remove_file(&output).ok();
+    res
+}
+
+fn run(files: &[PathBuf], script: &Path, output: &Path) -> Result<Vec<Vec<u8>>, String> {
     let comm = Command::new("blender")
-        .args(["--background","--python","./compiler/compile.py"])
-        .current_dir("./")
+        .arg("--background")
+        .arg("--python")
+        .arg(script)
+        .arg("--")
+        .arg(output)
+        .arg(pack::MAX_JOINTS.to_string())
+        .args(files)
         .stdout(Stdio::piped())
-        .spawn()
-        .expect("blender command failed, try to download blender, add the executable path to the system environment variables, \
-restart the computer and try again.");
-    let mut f = BufReader::new(comm.stdout.unwrap());
+        .spawn();
+    let mut comm = match comm {
+        Ok(v) => v,
+        Err(e) => return Err(format!("blender command failed: {}, fbx animations were not compiled. \
+Export them as gltf, or download blender, add the executable path to the system environment variables, \
+restart the computer and try again.", e))
+    };
+    let mut f = BufReader::new(comm.stdout.take().unwrap());
     let mut found_error = false;
     loop {
         let mut buf = String::new();
@@ -28,4 +59,11 @@ restart the computer and try again.");
             Err(e) => break println!("Error: {}", e)
         }
     }
+    comm.wait().ok();
+    if found_error {
+        return Err("blender reported errors while compiling the fbx animations".to_string())
+    }
+
+    let data = std::fs::read(output).unwrap_or_default();
+    Ok(crate::cache::read_records(&data))
 }
\ No newline at end of file
@@ -0,0 +1,283 @@
+use std::{path::{Path, PathBuf}, collections::HashMap, sync::{Mutex, OnceLock}};
+
+/// Directory inside the input directory where compiled records are kept between runs
+pub const CACHE_DIR: &str = ".cache";
+const MANIFEST: &str = "manifest";
+
+/// Hashes of everything a cached asset was compiled from
+#[derive(Clone, Copy, PartialEq)]
+struct Entry {
+    source: u64,
+    conf: u64,
+    compiler: u64
+}
+impl Entry {
+    fn new(input: &Path, source: &Path, dependencies: &[PathBuf], compiler: u64) -> Self {
+        let mut hash = Fnv::new();
+        for path in std::iter::once(&source.to_path_buf()).chain(dependencies.iter()) {
+            hash.write(relative(input, path).as_bytes());
+            hash.write(&std::fs::read(path).unwrap_or_default());
+        }
+        Self {
+            source: hash.0,
+            conf: Fnv::hash(&std::fs::read(source.parent().unwrap().join("compile.conf")).unwrap_or_default()),
+            compiler
+        }
+    }
+    fn key(&self) -> u64 {
+        let mut hash = Fnv::new();
+        hash.write(&self.source.to_be_bytes());
+        hash.write(&self.conf.to_be_bytes());
+        hash.write(&self.compiler.to_be_bytes());
+        hash.0
+    }
+}
+
+/// Compiled records of every source file, keyed by the hash of the file, of its `compile.conf` and of the compiler
+pub struct Cache {
+    input: PathBuf,
+    dir: PathBuf,
+    previous: HashMap<String, Entry>,
+    /// hash of the compiler build, see `compiler_hash`
+    compiler: u64,
+    current: Mutex<HashMap<String, Entry>>,
+    rebuilt: Mutex<Vec<String>>
+}
+impl Cache {
+    /// Opens the cache of the assets inside `input`
+    pub fn open(input: &Path) -> Self {
+        let dir = input.join(CACHE_DIR);
+        let mut previous = HashMap::new();
+        let manifest = std::fs::read_to_string(dir.join(MANIFEST)).unwrap_or_default();
+        for line in manifest.lines() {
+            let spl: Vec<&str> = line.split('\t').collect();
+            if spl.len() != 4 { continue }
+            let hashes: Vec<u64> = spl[1..].iter().filter_map(|v| u64::from_str_radix(v, 16).ok()).collect();
+            if hashes.len() != 3 { continue }
+            previous.insert(spl[0].to_string(), Entry { source: hashes[0], conf: hashes[1], compiler: hashes[2] });
+        }
+        Self {
+            input: input.to_path_buf(),
+            dir,
+            previous,
+            compiler: compiler_hash(),
+            current: Mutex::new(HashMap::new()),
+            rebuilt: Mutex::new(Vec::new())
+        }
+    }
+    /// Returns the cached records of `source` when none of its inputs changed, compiles them otherwise.
+    /// `dependencies` are the other files the records are built from, like gltf buffers.
+    /// A failed compile is not cached, the file is compiled again on the next run
+    pub fn get_or_compile(
+        &self,
+        source: &Path,
+        dependencies: &[PathBuf],
+        compile: impl FnOnce() -> Result<Vec<Vec<u8>>, String>
+    ) -> Result<Vec<Vec<u8>>, String> {
+        let name = relative(&self.input, source);
+        let entry = Entry::new(&self.input, source, dependencies, self.compiler);
+        let file = self.dir.join(format!("{:016x}", entry.key()));
+
+        let reason = match self.reason(&name, &entry) {
+            Some(v) => v,
+            None => match std::fs::read(&file) {
+                Ok(data) => {
+                    self.current.lock().unwrap().insert(name, entry);
+                    return Ok(read_records(&data))
+                },
+                Err(_) => "cache entry missing"
+            }
+        };
+        let records = compile()?;
+        self.current.lock().unwrap().insert(name.clone(), entry);
+        std::fs::create_dir_all(&self.dir).ok();
+        if let Err(e) = std::fs::write(&file, write_records(&records)) {
+            println!("Warning: can not write cache file {}, error: {}", file.display(), e)
+        }
+        self.rebuilt.lock().unwrap().push(format!("{} ({})", name, reason));
+        Ok(records)
+    }
+    /// Why `source` would be compiled again, None when its cached records are up to date
+    pub fn check(&self, source: &Path, dependencies: &[PathBuf]) -> Option<&'static str> {
+        let entry = Entry::new(&self.input, source, dependencies, self.compiler);
+        self.reason(&relative(&self.input, source), &entry).or_else(|| {
+            if self.dir.join(format!("{:016x}", entry.key())).is_file() { None } else { Some("cache entry missing") }
+        })
+    }
+    fn reason(&self, name: &str, entry: &Entry) -> Option<&'static str> {
+        match self.previous.get(name) {
+            None => Some("new file"),
+            Some(previous) if previous.source != entry.source => Some("source changed"),
+            Some(previous) if previous.conf != entry.conf => Some("compile.conf changed"),
+            Some(previous) if previous.compiler != entry.compiler => Some("compiler changed"),
+            Some(_) => None
+        }
+    }
+    /// Writes the manifest, deletes the entries of files that changed or no longer exist and reports what was rebuilt.
+    /// Files left out of this run by the include and exclude globs keep their entries
+    pub fn save(&self) {
+        let mut current = self.current.lock().unwrap().clone();
+        let compiled = current.len();
+        for (name, entry) in self.previous.iter() {
+            if !current.contains_key(name) && self.input.join(name).exists() {
+                current.insert(name.clone(), *entry);
+            }
+        }
+        let mut manifest: Vec<String> = current.iter().map(|(name, entry)| {
+            format!("{}\t{:016x}\t{:016x}\t{:016x}", name, entry.source, entry.conf, entry.compiler)
+        }).collect();
+        manifest.sort();
+        std::fs::create_dir_all(&self.dir).ok();
+        if let Err(e) = std::fs::write(self.dir.join(MANIFEST), manifest.join("\n")) {
+            println!("Warning: can not write the cache manifest, error: {}", e)
+        }
+
+        let keys: Vec<String> = current.values().map(|entry| format!("{:016x}", entry.key())).collect();
+        for file in std::fs::read_dir(&self.dir).into_iter().flatten().flatten() {
+            let file_name = file.file_name().to_string_lossy().to_string();
+            if file_name != MANIFEST && !keys.contains(&file_name) {
+                std::fs::remove_file(file.path()).ok();
+            }
+        }
+
+        let mut rebuilt = self.rebuilt.lock().unwrap();
+        rebuilt.sort();
+        println!("Rebuilt {} of {} assets", rebuilt.len(), compiled);
+        for v in rebuilt.iter() {
+            println!("\t{}", v)
+        }
+    }
+}
+
+/// Hash of the running executable, any change to the compiler or to the crates it is built with rebuilds every asset,
+/// not only the ones a version bump would
+fn compiler_hash() -> u64 {
+    // watch opens the cache on every change, the executable is only read once
+    static HASH: OnceLock<u64> = OnceLock::new();
+    *HASH.get_or_init(|| {
+        let mut hash = Fnv::new();
+        hash.write(format!("{} {}", env!("CARGO_PKG_VERSION"), pack::FORMAT_VERSION).as_bytes());
+        match std::env::current_exe().and_then(std::fs::read) {
+            Ok(v) => hash.write(&v),
+            Err(e) => println!("Warning: can not read the compiler executable, error: {}, assets compiled by another build may be reused", e)
+        }
+        hash.0
+    })
+}
+
+/// Cache entries are named by the path inside the input directory, the same whichever way the input is given
+fn relative(input: &Path, path: &Path) -> String {
+    let path = path.strip_prefix(input).unwrap_or(path);
+    path.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/")
+}
+
+/// Records prefixed by their big endian length, the layout of cache entries and of compile.py's output
+pub fn read_records(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut records = Vec::new();
+    let mut i = 0;
+    while i + 4 <= data.len() {
+        let len = u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]) as usize;
+        match data.get(i + 4..i + 4 + len) {
+            Some(record) => records.push(record.to_vec()),
+            None => break
+        }
+        i += 4 + len;
+    }
+    records
+}
+fn write_records(records: &[Vec<u8>]) -> Vec<u8> {
+    let mut res = Vec::with_capacity(records.iter().map(|record| record.len() + 4).sum());
+    for record in records {
+        res.extend_from_slice(&(record.len() as u32).to_be_bytes());
+        res.extend_from_slice(record);
+    }
+    res
+}
+
+/// 64 bit FNV-1a
+struct Fnv(u64);
+impl Fnv {
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+    fn hash(bytes: &[u8]) -> u64 {
+        let mut hash = Self::new();
+        hash.write(bytes);
+        hash.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::Cache;
+
+    fn input(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("compiler_cache_{}_{}", std::process::id(), name));
+        std::fs::remove_dir_all(&dir).ok();
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+    /// Compiles `source` with a new cache, returns the records and whether they were compiled or read from the cache
+    fn compile(input: &Path, source: &Path, dependencies: &[PathBuf]) -> (Vec<Vec<u8>>, bool) {
+        let cache = Cache::open(input);
+        let mut compiled = false;
+        let records = cache.get_or_compile(source, dependencies, || {
+            compiled = true;
+            Ok(vec![std::fs::read(source).unwrap()])
+        }).unwrap();
+        cache.save();
+        (records, compiled)
+    }
+
+    #[test]
+    fn content_changes_invalidate_the_cache() {
+        let input = input("content");
+        let source = input.join("a.gltf");
+        let buffer = input.join("a.bin");
+        std::fs::write(&source, "first").unwrap();
+        std::fs::write(&buffer, "buffer").unwrap();
+        let deps = [buffer.clone()];
+        assert_eq!(compile(&input, &source, &deps), (vec![b"first".to_vec()], true));
+        assert_eq!(compile(&input, &source, &deps), (vec![b"first".to_vec()], false));
+
+        std::fs::write(&source, "second").unwrap();
+        assert_eq!(compile(&input, &source, &deps), (vec![b"second".to_vec()], true));
+        assert_eq!(Cache::open(&input).check(&source, &deps), None);
+
+        std::fs::write(&buffer, "new buffer").unwrap();
+        assert_eq!(Cache::open(&input).check(&source, &deps), Some("source changed"));
+        assert!(compile(&input, &source, &deps).1);
+
+        std::fs::write(input.join("compile.conf"), "Scale = 2").unwrap();
+        assert_eq!(Cache::open(&input).check(&source, &deps), Some("compile.conf changed"));
+        assert!(compile(&input, &source, &deps).1);
+        assert!(!compile(&input, &source, &deps).1);
+
+        // another build of the compiler
+        let mut cache = Cache::open(&input);
+        cache.compiler ^= 1;
+        assert_eq!(cache.check(&source, &deps), Some("compiler changed"));
+        std::fs::remove_dir_all(input).ok();
+    }
+
+    #[test]
+    fn failed_compiles_are_not_cached() {
+        let input = input("failed");
+        let source = input.join("a.png");
+        std::fs::write(&source, "image").unwrap();
+        let cache = Cache::open(&input);
+        assert_eq!(cache.get_or_compile(&source, &[], || Err("broken".to_string())), Err("broken".to_string()));
+        cache.save();
+        assert_eq!(Cache::open(&input).check(&source, &[]), Some("new file"));
+        assert!(compile(&input, &source, &[]).1);
+        std::fs::remove_dir_all(input).ok();
+    }
+}
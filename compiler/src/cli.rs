@@ -0,0 +1,140 @@
+use std::path::{Path, PathBuf};
+
+pub const USAGE: &str = "\
+Usage: compiler [options]
+
+Options:
+    -i, --input <dir>         directory of the source assets, ./assets/ by default
+    -o, --output <file>       pack to write, compiled.bin inside the input directory by default
+        --include <glob>      only compile the files matching the glob, can be repeated
+        --exclude <glob>      skip the files matching the glob, can be repeated
+        --list                print what would be compiled, with sizes, without compiling
+        --watch               recompile on every change and hot reload the running game
+    -h, --help                print this message
+
+Globs are matched against paths relative to the input directory, `*` and `?` stay inside
+a directory, `**` crosses them, a glob without `/` is matched against the file name.
+Exits with 1 when an asset or a compile.conf fails, with 2 on invalid arguments.";
+
+pub struct Args {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub list: bool,
+    pub watch: bool,
+    pub help: bool
+}
+impl Args {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let mut input = None;
+        let mut output = None;
+        let mut res = Self {
+            input: PathBuf::new(),
+            output: PathBuf::new(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            list: false,
+            watch: false,
+            help: false
+        };
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or(format!("{} needs a value", arg));
+            match arg.as_str() {
+                "-i" | "--input" => input = Some(PathBuf::from(value()?)),
+                "-o" | "--output" => output = Some(PathBuf::from(value()?)),
+                "--include" => res.include.push(value()?),
+                "--exclude" => res.exclude.push(value()?),
+                "--list" => res.list = true,
+                "--watch" => res.watch = true,
+                "-h" | "--help" => res.help = true,
+                _ => return Err(format!("unknown argument \"{}\"", arg))
+            }
+        }
+        if res.list && res.watch {
+            return Err("--list and --watch can not be used together".to_string())
+        }
+        res.input = input.unwrap_or_else(|| PathBuf::from("./assets/"));
+        res.output = output.unwrap_or_else(|| res.input.join("compiled.bin"));
+        Ok(res)
+    }
+    /// Whether a source file passes the include and exclude globs
+    pub fn filter(&self, path: &Path) -> bool {
+        let relative = path.strip_prefix(&self.input).unwrap_or(path);
+        let relative = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        let matches = |glob: &String| if glob.contains('/') {
+            glob_match(glob.trim_start_matches("./"), &relative)
+        } else {
+            glob_match(glob, relative.rsplit('/').next().unwrap())
+        };
+        (self.include.is_empty() || self.include.iter().any(matches)) && !self.exclude.iter().any(matches)
+    }
+}
+
+fn glob_match(glob: &str, path: &str) -> bool {
+    match glob.strip_prefix("**") {
+        Some(rest) => {
+            let rest = rest.strip_prefix('/').unwrap_or(rest);
+            if rest.is_empty() { return true }
+            (0..=path.len()).filter(|i| *i == 0 || path.as_bytes()[i - 1] == b'/')
+                .any(|i| glob_match(rest, &path[i..]))
+        },
+        None => {
+            let mut chars = glob.chars();
+            match chars.next() {
+                None => path.is_empty(),
+                Some('*') => {
+                    let rest = chars.as_str();
+                    let segment = path.find('/').unwrap_or(path.len());
+                    (0..=segment).filter(|i| path.is_char_boundary(*i)).any(|i| glob_match(rest, &path[i..]))
+                },
+                Some(c) => {
+                    let mut path_chars = path.chars();
+                    match path_chars.next() {
+                        Some(p) if p == c || (c == '?' && p != '/') => glob_match(chars.as_str(), path_chars.as_str()),
+                        _ => false
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{Args, glob_match};
+
+    fn args(args: &[&str]) -> Result<Args, String> {
+        Args::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob_match("*.png", "rock.png"));
+        assert!(!glob_match("*.png", "terrains/rock.png"));
+        assert!(glob_match("**/*.png", "terrains/rock.png"));
+        assert!(glob_match("**/*.png", "rock.png"));
+        assert!(glob_match("characters/**", "characters/ch/ch.gltf"));
+        assert!(glob_match("ch_?.gltf", "ch_a.gltf"));
+        assert!(!glob_match("ch?gltf", "ch/gltf"));
+    }
+
+    #[test]
+    fn filters() {
+        let args = args(&["-i", "assets", "--include", "characters/**", "--exclude", "*.png"]).unwrap();
+        assert!(args.filter(Path::new("assets/characters/ch/ch.gltf")));
+        assert!(!args.filter(Path::new("assets/characters/ch/ch_diffuse.png")));
+        assert!(!args.filter(Path::new("assets/terrains/terrain_01.gltf")));
+        assert_eq!(args.output, Path::new("assets/compiled.bin"));
+    }
+
+    #[test]
+    fn invalid_arguments() {
+        assert!(args(&["--input"]).is_err_and(|e| e.contains("needs a value")));
+        assert!(args(&["--fast"]).is_err_and(|e| e.contains("unknown argument")));
+        assert!(args(&["--list", "--watch"]).is_err());
+    }
+}
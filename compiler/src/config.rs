@@ -1,34 +1,320 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-pub enum VertexType {
-    Basic, NUS, U, NU
+use pack::{VertexType, TextureFormat, ColorSpace, Compression};
+
+pub const CONFIG_FILE: &str = "compile.conf";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpAxis {
+    Y,
+    /// rotated to y up, for files exported from tools that keep z up
+    Z
 }
 
+/// Settings of one source file: the directory's `compile.conf` lines, then the lines of its `[file name]` section.
+///
+/// ```text
+/// VertexType = NUS
+/// Quantize = true
+/// Lods = 2
+/// LodDistance = 15
+/// Scale = 0.01
+/// FramesPerSecond = 30
+/// [level_01.gltf]
+/// Scene = true
+/// Pack = characters
+/// [ch_diffuse.png]
+/// TextureFormat = BC1
+/// Compression = Zstd
+/// ```
+#[derive(Clone)]
 pub struct Config {
-    pub vertex_type: VertexType
+    pub vertex_type: VertexType,
+    /// `NU` and `NUS` meshes are written with compact attributes, as `NUQ` and `NUSQ`
+    pub quantize: bool,
+    /// simplified levels generated for every mesh, fewer when a level can not get smaller within `lod_error`
+    pub lods: u8,
+    /// triangles every level keeps, relative to the previous level
+    pub lod_ratio: f32,
+    /// largest deviation of a level from the full mesh, relative to the size of the mesh
+    pub lod_error: f32,
+    /// distance from the camera the first level is drawn from, doubled for every next one
+    pub lod_distance: f32,
+    /// the gltf is compiled as a scene record of its nodes and a mesh record for each of its meshes, instead of one mesh
+    pub scene: bool,
+    /// uniform scale applied to meshes and animations, the animations of a mesh need the same one
+    pub scale: f32,
+    pub up_axis: UpAxis,
+    /// rate at which gltf animation channels are sampled, blender's default scene frame rate unless set
+    pub frames_per_second: f32,
+    /// BC1 textures with an alpha channel are stored as BC3
+    pub texture_format: TextureFormat,
+    pub mips: bool,
+    /// forced color space, otherwise picked from the texture name
+    pub color_space: Option<ColorSpace>,
+    /// textures sampled without sRGB conversion, besides the ones named like `*_normal`
+    pub linear_textures: Vec<String>,
+    /// replaces the file name as the asset name, only allowed inside a file section
+    pub name: Option<String>,
+    /// pack the records go to, `<name>.bin` next to the output pack, the output pack itself when not set
+    pub pack: Option<String>,
+    /// how the records are stored in the pack, the game decompresses them while loading
+    pub compression: Compression
+}
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            vertex_type: VertexType::Basic,
+            quantize: false,
+            lods: 0,
+            lod_ratio: 0.5,
+            lod_error: 0.02,
+            lod_distance: 10.,
+            scene: false,
+            scale: 1.,
+            up_axis: UpAxis::Y,
+            frames_per_second: 24.,
+            texture_format: TextureFormat::Raw,
+            mips: true,
+            color_space: None,
+            linear_textures: Vec::new(),
+            name: None,
+            pack: None,
+            compression: Compression::None
+        }
+    }
 }
 impl Config {
-    pub fn new(path: impl AsRef<Path>) -> Self {
-        let path = path.as_ref().join("compile.conf");
-        let mut res = Self {
-            vertex_type: VertexType::Basic
-        };
-        let data = match std::fs::read_to_string(&path) {
+    /// Settings of `file`, read from the `compile.conf` next to it
+    pub fn new(file: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let file = file.as_ref();
+        let path = file.parent().unwrap().join(CONFIG_FILE);
+        let file_name = file.file_name().unwrap().to_string_lossy().to_string();
+        let mut res = Self::default();
+        for line in parse(&path)? {
+            if line.section.is_none() || line.section.as_ref() == Some(&file_name) {
+                res.apply(&line).map_err(|message| ConfigError { path: path.clone(), line: line.number, message })?
+            }
+        }
+        Ok(res)
+    }
+    /// Checks every line of a `compile.conf`, including the sections of files it does not apply to
+    pub fn check(path: impl AsRef<Path>) -> Vec<ConfigError> {
+        let path = path.as_ref();
+        let lines = match parse(path) {
             Ok(v) => v,
-            Err(_) => return res
+            Err(e) => return vec![e]
         };
-        for line in data.lines() {
-            let mut spl = line.split('='); 
-            match spl.next().unwrap().trim() {
-                "VertexType" => match spl.next().unwrap().trim() {
-                    "NUS" => res.vertex_type = VertexType::NUS,
-                    "NU" => res.vertex_type = VertexType::NU,
-                    "U" => res.vertex_type = VertexType::U,
-                    _ => {}
+        lines.iter().filter_map(|line| {
+            Self::default().apply(line).err().map(|message| ConfigError { path: path.to_path_buf(), line: line.number, message })
+        }).collect()
+    }
+    fn apply(&mut self, line: &Line) -> Result<(), String> {
+        let value = line.value.as_str();
+        match line.key.as_str() {
+            "VertexType" => self.vertex_type = match VertexType::from_name(value) {
+                Some(v) if !v.is_quantized() => v,
+                Some(_) => return Err(format!("invalid VertexType \"{}\", use Quantize = true for quantized vertices", value)),
+                None => return Err(format!("invalid VertexType \"{}\", expected Basic, U, NU, NUS, NUT or NUST", value))
+            },
+            "Scale" => self.scale = match value.parse::<f32>() {
+                Ok(v) if v.is_finite() && v > 0. => v,
+                _ => return Err(format!("invalid Scale \"{}\", expected a number above 0", value))
+            },
+            "UpAxis" => self.up_axis = match value {
+                "Y" => UpAxis::Y,
+                "Z" => UpAxis::Z,
+                _ => return Err(format!("invalid UpAxis \"{}\", expected Y or Z", value))
+            },
+            "FramesPerSecond" => self.frames_per_second = match value.parse::<f32>() {
+                Ok(v) if v.is_finite() && v > 0. => v,
+                _ => return Err(format!("invalid FramesPerSecond \"{}\", expected a number above 0", value))
+            },
+            "TextureFormat" => self.texture_format = match TextureFormat::from_name(value) {
+                Some(v) => v,
+                None => return Err(format!("invalid TextureFormat \"{}\", expected RAW, BC1 or BC3", value))
+            },
+            "Quantize" => self.quantize = match value {
+                "true" => true,
+                "false" => false,
+                _ => return Err(format!("invalid Quantize \"{}\", expected true or false", value))
+            },
+            "Lods" => self.lods = match value.parse::<u8>() {
+                Ok(v) => v,
+                _ => return Err(format!("invalid Lods \"{}\", expected a whole number from 0 to 255", value))
+            },
+            "LodRatio" => self.lod_ratio = match value.parse::<f32>() {
+                Ok(v) if v > 0. && v < 1. => v,
+                _ => return Err(format!("invalid LodRatio \"{}\", expected a number between 0 and 1", value))
+            },
+            "LodError" => self.lod_error = match value.parse::<f32>() {
+                Ok(v) if v > 0. && v <= 1. => v,
+                _ => return Err(format!("invalid LodError \"{}\", expected a number above 0, up to 1", value))
+            },
+            "LodDistance" => self.lod_distance = match value.parse::<f32>() {
+                Ok(v) if v.is_finite() && v > 0. => v,
+                _ => return Err(format!("invalid LodDistance \"{}\", expected a number above 0", value))
+            },
+            "Scene" => self.scene = match value {
+                "true" => true,
+                "false" => false,
+                _ => return Err(format!("invalid Scene \"{}\", expected true or false", value))
+            },
+            "Mips" => self.mips = match value {
+                "true" => true,
+                "false" => false,
+                _ => return Err(format!("invalid Mips \"{}\", expected true or false", value))
+            },
+            "ColorSpace" => self.color_space = match value {
+                "sRGB" => Some(ColorSpace::Srgb),
+                "Linear" => Some(ColorSpace::Linear),
+                _ => return Err(format!("invalid ColorSpace \"{}\", expected sRGB or Linear", value))
+            },
+            "LinearTextures" => self.linear_textures = value.split(',')
+                .map(|texture| texture.trim().to_string())
+                .filter(|texture| !texture.is_empty())
+                .collect(),
+            "Name" => {
+                if line.section.is_none() {
+                    return Err("Name can only be set inside a [file] section".to_string())
+                }
+                if value.is_empty() || value.contains('#') {
+                    return Err(format!("invalid Name \"{}\", it can not be empty or contain #", value))
+                }
+                self.name = Some(value.to_string())
+            },
+            "Pack" => {
+                if value.is_empty() || value.contains(['/', '\\', '.', '#']) {
+                    return Err(format!("invalid Pack \"{}\", expected a file name without extension", value))
+                }
+                self.pack = Some(value.to_string())
+            },
+            "Compression" => self.compression = match Compression::from_name(value) {
+                Some(v) => v,
+                None => return Err(format!("invalid Compression \"{}\", expected None, Zstd or LZ4", value))
+            },
+            key => return Err(format!("unknown setting \"{}\"", key))
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct ConfigError {
+    pub path: PathBuf,
+    pub line: usize,
+    pub message: String
+}
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.path.display(), self.line, self.message)
+    }
+}
+
+struct Line {
+    section: Option<String>,
+    key: String,
+    value: String,
+    number: usize
+}
+
+/// Reads `key = value` lines and `[file name]` section headers, `//` starts a comment
+fn parse(path: &Path) -> Result<Vec<Line>, ConfigError> {
+    let data = match std::fs::read_to_string(path) {
+        Ok(v) => v,
+        Err(_) => return Ok(Vec::new())
+    };
+    let mut res = Vec::new();
+    let mut section = None;
+    for (i, line) in data.lines().enumerate() {
+        let error = |message: String| ConfigError { path: path.to_path_buf(), line: i + 1, message };
+        let line = line.split("//").next().unwrap().trim();
+        if line.is_empty() { continue }
+        if let Some(name) = line.strip_prefix('[') {
+            match name.strip_suffix(']') {
+                Some(name) if !name.trim().is_empty() => {
+                    let name = name.trim();
+                    if !path.with_file_name(name).is_file() {
+                        return Err(error(format!("section [{}] does not match any file next to this {}", name, CONFIG_FILE)))
+                    }
+                    section = Some(name.to_string())
                 },
-                _ => {}
+                _ => return Err(error(format!("invalid section \"{}\", expected [file name]", line)))
             }
+            continue
+        }
+        match line.split_once('=') {
+            Some((key, value)) => res.push(Line {
+                section: section.clone(),
+                key: key.trim().to_string(),
+                value: value.trim().to_string(),
+                number: i + 1
+            }),
+            None => return Err(error(format!("expected key = value, found \"{}\"", line)))
         }
-        res
     }
-}
\ No newline at end of file
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{Config, UpAxis, CONFIG_FILE};
+
+    /// Directory with a compile.conf and empty source files
+    fn dir(name: &str, conf: &str, files: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("compiler_config_{}_{}", std::process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(CONFIG_FILE), conf).unwrap();
+        for file in files {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn sections_only_apply_to_their_file() {
+        let dir = dir("sections", "Scale = 0.01 // centimeters\nUpAxis = Z\n[a.gltf]\nScale = 2\nName = hero\nFramesPerSecond = 30\n[b.gltf]\nScene = true", &["a.gltf", "b.gltf"]);
+        let a = Config::new(dir.join("a.gltf")).unwrap();
+        assert_eq!((a.scale, a.up_axis, a.name.as_deref(), a.scene, a.frames_per_second), (2., UpAxis::Z, Some("hero"), false, 30.));
+        let b = Config::new(dir.join("b.gltf")).unwrap();
+        assert_eq!((b.scale, b.name, b.scene, b.frames_per_second), (0.01, None, true, 24.));
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn errors_have_their_line() {
+        let dir = dir("errors", "VertexType = NUS\n\nScale = -1\nLods = many\nName = hero\nColour = red", &[]);
+        let errors = Config::check(dir.join(CONFIG_FILE));
+        assert_eq!(errors.iter().map(|e| e.line).collect::<Vec<_>>(), [3, 4, 5, 6]);
+        assert!(errors[0].message.contains("invalid Scale"));
+        assert!(errors[2].message.contains("[file] section"));
+        assert!(errors[3].message.contains("unknown setting \"Colour\""));
+        assert!(Config::new(dir.join("a.gltf")).is_err());
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn malformed_lines_stop_the_parse() {
+        let malformed = dir("malformed", "Scale = 2\nScene true", &[]);
+        let errors = Config::check(malformed.join(CONFIG_FILE));
+        assert_eq!((errors.len(), errors[0].line), (1, 2));
+        assert!(errors[0].message.contains("expected key = value"));
+        std::fs::remove_dir_all(malformed).ok();
+
+        let missing = dir("missing_file", "[missing.gltf]\nScene = true", &[]);
+        let errors = Config::check(missing.join(CONFIG_FILE));
+        assert!(errors[0].message.contains("does not match any file"));
+        std::fs::remove_dir_all(missing).ok();
+    }
+
+    #[test]
+    fn no_config_is_the_default() {
+        let dir = std::env::temp_dir().join(format!("compiler_config_{}_none", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let conf = Config::new(dir.join("a.png")).unwrap();
+        assert_eq!((conf.scale, conf.mips, conf.pack), (1., true, None));
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
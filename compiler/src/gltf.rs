@@ -1,168 +1,691 @@
-use std::{path::Path, time::Instant};
+use std::{path::{Path, PathBuf}, time::Instant, collections::HashMap};
 
-use cgmath::{Matrix4, SquareMatrix};
+use cgmath::{Matrix4, SquareMatrix, Quaternion, Vector3, Matrix3, InnerSpace, VectorSpace, Deg};
+use gltf::animation::{Interpolation, util::ReadOutputs};
 
-use crate::{writer::Writer, config::{VertexType, Config}};
+use pack::{MaterialRecord, AlphaMode, MeshRecord, SubMeshRecord, Vertices, Indices, VertexType, VertexBasic, VertexU, VertexNU, VertexNUS, VertexNUT, VertexNUST, JointRecord, AnimationRecord, JointPose, SceneRecord, SceneNode, NO_JOINT, MAX_JOINTS, Slice, Bounds, transform_point};
 
-pub fn file(path: impl AsRef<Path>) -> Vec<u8> {
+use crate::config::{Config, UpAxis};
+
+/// Buffers a gltf file loads from other files, its records change when they do
+pub fn dependencies(path: &Path) -> Vec<PathBuf> {
+    let gltf = match gltf::Gltf::open(path) { Ok(v)=>v, Err(_) => return Vec::new() };
+    gltf.buffers().filter_map(|buffer| match buffer.source() {
+        gltf::buffer::Source::Uri(uri) if !uri.starts_with("data:") => Some(path.parent().unwrap().join(uri)),
+        _ => None
+    }).collect()
+}
+
+/// Compiles the meshes of a gltf file into one mesh record, followed by a record for each of its materials
+pub fn file(path: impl AsRef<Path>) -> Result<Vec<Vec<u8>>, String> {
     let start = Instant::now();
     let path = path.as_ref();
-    let mut res = Writer(Vec::new());
-    res.0.push(b'M');
-    res.append_string(path.with_extension("").file_name().unwrap().to_string_lossy().to_string());
-
-    let conf = Config::new(path.parent().unwrap());
-
-    let (gltf, buffers, _) = match gltf::import(path) { Ok(v)=>v, Err(e) => panic!("{}, {:?}", e, path.display()) };
-    match conf.vertex_type {
-        VertexType::Basic => write_vertices(&mut res, &conf, &gltf, &buffers, |res, indices, ps, _, _, _, _| {
-            let mut i = 0;
-            let l = indices.len();
-            while i < l {
-                let idx = indices[i] as usize;
-                res.append_vec3_f32(ps[idx]);
-                i += 1;
-            }
+    let conf = match Config::new(path) { Ok(v)=>v, Err(e) => return Err(e.to_string()) };
+    let name = conf.name.clone().unwrap_or_else(|| path.with_extension("").file_name().unwrap().to_string_lossy().to_string());
+
+    let (gltf, buffers, _) = match gltf::import(path) { Ok(v)=>v, Err(e) => return Err(e.to_string()) };
+    let primitives = read_primitives(gltf.meshes(), &buffers, &name)?;
+    let skin = mesh_skin(&gltf, |_| true)?;
+    let mesh = mesh_record(name.clone(), primitives, skin, &buffers, &conf, path)?;
+    println!("gltf mesh: {}, compiled in: {:.2} sec", path.display(), (Instant::now() - start).as_secs_f64());
+    print_lods(&mesh);
+    let mut records = vec![mesh.encode()];
+    for material in gltf.materials() {
+        records.push(material_record(&material, &name, conf.vertex_type, path).encode());
+    }
+    Ok(records)
+}
+
+/// Compiles a gltf file as a scene: a record for each of its meshes, in their own space, one for each of its materials
+/// and a scene record with the nodes of its default scene, which reference the meshes as `<name>_<mesh name>`
+pub fn scene(path: impl AsRef<Path>) -> Result<Vec<Vec<u8>>, String> {
+    let path = path.as_ref();
+    let conf = match Config::new(path) { Ok(v)=>v, Err(e) => return Err(e.to_string()) };
+    let name = conf.name.clone().unwrap_or_else(|| path.with_extension("").file_name().unwrap().to_string_lossy().to_string());
+
+    let (gltf, buffers, _) = match gltf::import(path) { Ok(v)=>v, Err(e) => return Err(e.to_string()) };
+    let mut records = Vec::new();
+    let mut meshes_names: Vec<String> = Vec::with_capacity(gltf.meshes().len());
+    for mesh in gltf.meshes() {
+        let start = Instant::now();
+        let base = format!("{}_{}", name, mesh.name().map(str::to_string).unwrap_or_else(|| mesh.index().to_string()));
+        // gltf mesh names do not have to be unique, and another mesh can already be named like a suffixed one
+        let mut mesh_name = base.clone();
+        let mut suffix = mesh.index();
+        while meshes_names.contains(&mesh_name) {
+            mesh_name = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        let index = mesh.index();
+        let primitives = read_primitives(std::iter::once(mesh), &buffers, &name)?;
+        let skin = mesh_skin(&gltf, |mesh| mesh == index)?;
+        let mesh = mesh_record(mesh_name.clone(), primitives, skin, &buffers, &conf, path)?;
+        println!("gltf mesh: {} ({}), compiled in: {:.2} sec", path.display(), mesh_name, (Instant::now() - start).as_secs_f64());
+        print_lods(&mesh);
+        records.push(mesh.encode());
+        meshes_names.push(mesh_name);
+    }
+    for material in gltf.materials() {
+        records.push(material_record(&material, &name, conf.vertex_type, path).encode());
+    }
+    match gltf.default_scene().or_else(|| gltf.scenes().next()) {
+        Some(scene) => records.push(scene_record(name, &scene, &meshes_names, root_transform(&conf)).encode()),
+        None => return Err("the file has no scene".to_string())
+    }
+    Ok(records)
+}
+
+/// Nodes of a scene in depth first order, the root transform is applied around every local transform
+/// so the nodes line up with the meshes it was applied to
+fn scene_record(name: String, scene: &gltf::Scene, meshes_names: &[String], root: Matrix4<f32>) -> SceneRecord {
+    fn add(node: gltf::Node, meshes_names: &[String], root: Matrix4<f32>, nodes: &mut Vec<SceneNode>) -> u16 {
+        let id = nodes.len();
+        let local = root * Matrix4::from(node.transform().matrix()) * root.invert().unwrap();
+        let (translation, rotation, scale) = decompose(local);
+        nodes.push(SceneNode {
+            name: node.name().unwrap_or_default().to_string(),
+            translation,
+            rotation,
+            scale,
+            mesh: node.mesh().map(|mesh| meshes_names[mesh.index()].clone()),
+            extras: node.extras().as_ref().map(|extras| extras.get().to_string()).unwrap_or_default(),
+            children: Vec::new()
+        });
+        for child in node.children() {
+            let child = add(child, meshes_names, root, nodes);
+            nodes[id].children.push(child);
+        }
+        id as u16
+    }
+    let mut nodes = Vec::new();
+    let roots = scene.nodes().map(|node| add(node, meshes_names, root, &mut nodes)).collect();
+    SceneRecord { name, nodes, roots }
+}
+
+/// Translation, x y z w quaternion and scale of an affine transform without shear
+fn decompose(m: Matrix4<f32>) -> ([f32;3], [f32;4], [f32;3]) {
+    let mut r = Matrix3::from_cols(m.x.truncate(), m.y.truncate(), m.z.truncate());
+    let mut scale = [r.x.magnitude(), r.y.magnitude(), r.z.magnitude()];
+    // a mirrored transform keeps a rotation by flipping one of its axes
+    if r.determinant() < 0. {
+        scale[0] = -scale[0];
+    }
+    r.x /= scale[0];
+    r.y /= scale[1];
+    r.z /= scale[2];
+    let q = Quaternion::from(r).normalize();
+    (m.w.truncate().into(), [q.v.x, q.v.y, q.v.z, q.s], scale)
+}
+
+fn print_lods(mesh: &MeshRecord) {
+    if mesh.lods.is_empty() { return }
+    let mut levels = vec![mesh.sub_meshes.iter().map(|sub_mesh| sub_mesh.indices_len as usize).sum::<usize>() / 3];
+    levels.extend(mesh.lods.iter().map(|lod| lod.indices.iter().map(|indices| indices.len()).sum::<usize>() / 3));
+    println!("\tlevels of detail, triangles: {}", levels.iter().map(usize::to_string).collect::<Vec<_>>().join(" -> "));
+}
+
+/// Skin of the nodes drawing the meshes `meshes` accepts, they can not use different skins
+fn mesh_skin(gltf: &gltf::Document, meshes: impl Fn(usize) -> bool) -> Result<Option<gltf::Skin<'_>>, String> {
+    let mut res: Option<gltf::Skin<'_>> = None;
+    for node in gltf.nodes().filter(|node| node.mesh().is_some_and(|mesh| meshes(mesh.index()))) {
+        match (node.skin(), res.as_ref()) {
+            (Some(skin), Some(other)) if skin.index() != other.index() => return Err(format!(
+                "meshes compiled together are skinned by skins {} and {}, compile the file as a scene or bind them to one skin",
+                other.index(), skin.index())),
+            (Some(skin), None) => res = Some(skin),
+            _ => {}
+        }
+    }
+    Ok(res)
+}
+
+/// Builds a mesh record from gltf primitives, moved by the root transform of the config. `skin` holds the joints of skinned vertex types
+fn mesh_record(
+    name: String,
+    mut primitives: Vec<Primitive>,
+    skin: Option<gltf::Skin>,
+    buffers: &[gltf::buffer::Data],
+    conf: &Config,
+    path: &Path
+) -> Result<MeshRecord, String> {
+    check_attributes(&primitives, conf.vertex_type)?;
+    let root = root_transform(conf);
+    transform_primitives(&mut primitives, root);
+    if conf.vertex_type.has_tangents() {
+        for primitive in primitives.iter_mut() {
+            crate::tangents::generate(primitive, path)?
+        }
+    }
+    let (vertices, mut indices) = match conf.vertex_type {
+        VertexType::Basic => collect_vertices(&primitives, Vertices::Basic, |p, i| VertexBasic {
+            position: p.positions[i]
         }),
-        VertexType::U => write_vertices(&mut res, &conf, &gltf, &buffers, |res, indices, ps, _, uvs, _, _| {
-            let uvs = uvs.unwrap();
-            let mut i = 0;
-            let l = indices.len();
-            while i < l {
-                let idx = indices[i] as usize;
-                res.append_vec3_f32(ps[idx]);
-                res.append_vec2_f32(uvs[idx]);
-                i += 1;
-            }
+        VertexType::U => collect_vertices(&primitives, Vertices::U, |p, i| VertexU {
+            position: p.positions[i],
+            uv: p.uvs.as_ref().unwrap()[i]
         }),
-        VertexType::NU => write_vertices(&mut res, &conf, &gltf, &buffers, |res, indices, ps, ns, uvs, _, _| {
-            let ns = ns.unwrap();
-            let uvs = uvs.unwrap();
-            let mut i = 0;
-            let l = indices.len();
-            while i < l {
-                let idx = indices[i] as usize;
-                res.append_vec3_f32(ps[idx]);
-                res.append_vec3_f32(ns[idx]);
-                res.append_vec2_f32(uvs[idx]);
-                i += 1;
-            }
+        VertexType::NU => collect_vertices(&primitives, Vertices::NU, |p, i| VertexNU {
+            position: p.positions[i],
+            normal: p.normals.as_ref().unwrap()[i],
+            uv: p.uvs.as_ref().unwrap()[i]
         }),
-        VertexType::NUS => write_vertices(&mut res, &conf, &gltf, &buffers, |res, indices, ps, ns, uvs, js, ws| {
-            let ns = ns.unwrap();
-            let uvs = uvs.unwrap();
-            let js = js.unwrap();
-            let ws = ws.unwrap();
-            let mut i = 0;
-            let l = indices.len();
-            while i < l {
-                let idx = indices[i] as usize;
-                res.append_vec3_f32(ps[idx]);
-                res.append_vec3_f32(ns[idx]);
-                res.append_vec2_f32(uvs[idx]);
-                res.append_joints(js[idx]);
-                res.append_vec4_f32(ws[idx]);
-                i += 1;
-            }
-        })
-    }
-    match conf.vertex_type {
-        VertexType::NUS => {
-            let skin = gltf.skins().next().unwrap();
-            let reader = skin.reader(|buffer| Some(&buffers[buffer.index()]));
-            let ibms: Vec<[[f32; 4]; 4]> = reader.read_inverse_bind_matrices().unwrap().collect();
-            let joints: Vec<gltf::Node> = skin.joints().collect();
-
-            let mut joints_poses = Vec::with_capacity(joints.len());
-            for joint_id in 0..joints.len() {
-                joints_poses.push(Matrix4::from(ibms[joint_id]).invert().unwrap());
-            }
-            let mut joints_poses_local = joints_poses.clone();
-            let mut joints_parents = Vec::with_capacity(joints.len());
-            for (joint_id, joint) in joints.iter().enumerate() {
-                let mut parent = get_gltf_node_parent_id(&joints, &joint);
-                if parent != 255 {
-                    joints_poses_local[joint_id] = joints_poses[joint_id] * Matrix4::from(ibms[parent as usize])
-                }
-                let mut parents = Vec::new();
-                loop {
-                    if parent == 255 { break }
-                    parents.push(parent);
-                    parent = get_gltf_node_parent_id(&joints, &joints[parent as usize]);
-                }
-                parents.reverse();
-                joints_parents.push(parents);
-            }
+        VertexType::NUS => collect_vertices(&primitives, Vertices::NUS, |p, i| VertexNUS {
+            position: p.positions[i],
+            normal: p.normals.as_ref().unwrap()[i],
+            uv: p.uvs.as_ref().unwrap()[i],
+            joints: p.joints.as_ref().unwrap()[i].map(|joint| joint as u32),
+            weights: p.weights.as_ref().unwrap()[i]
+        }),
+        VertexType::NUT => collect_vertices(&primitives, Vertices::NUT, |p, i| VertexNUT {
+            position: p.positions[i],
+            normal: p.normals.as_ref().unwrap()[i],
+            uv: p.uvs.as_ref().unwrap()[i],
+            tangent: p.tangents.as_ref().unwrap()[i]
+        }),
+        VertexType::NUST => collect_vertices(&primitives, Vertices::NUST, |p, i| VertexNUST {
+            position: p.positions[i],
+            normal: p.normals.as_ref().unwrap()[i],
+            uv: p.uvs.as_ref().unwrap()[i],
+            tangent: p.tangents.as_ref().unwrap()[i],
+            joints: p.joints.as_ref().unwrap()[i].map(|joint| joint as u32),
+            weights: p.weights.as_ref().unwrap()[i]
+        }),
+        VertexType::NUQ | VertexType::NUSQ => unreachable!("compile.conf can not set quantized vertex types")
+    };
+    let mut indices_start = 0;
+    let sub_meshes: Vec<SubMeshRecord> = primitives.iter().map(|primitive| {
+        let sub_mesh = SubMeshRecord {
+            material: primitive.material.clone(),
+            indices_start,
+            indices_len: primitive.indices.len() as u32,
+            bounds: Bounds::from_points(primitive.indices.iter().map(|i| primitive.positions[*i as usize]))
+        };
+        indices_start += sub_mesh.indices_len;
+        sub_mesh
+    }).collect();
+    let bounds = Bounds::from_points(primitives.iter().flat_map(|p| p.indices.iter().map(|i| p.positions[*i as usize])));
+    let lods = crate::lod::generate(&vertices, &mut indices, &sub_meshes, conf)?;
+    let vertices = match conf.quantize.then(|| vertices.quantize()) {
+        Some(Some(v)) => v,
+        Some(None) => {
+            println!("Warning: {} can not be quantized, its uvs go past 0 to 1 or it is not NU or NUS, it is stored as {}",
+                path.display(), vertices.vertex_type().name());
+            vertices
+        },
+        None => vertices
+    };
+    let joints = match (conf.vertex_type.has_joints(), skin) {
+        (true, Some(skin)) => read_joints(&skin, buffers, root, &primitives)?,
+        (true, None) => return Err("the mesh has joints but no node binds it to a skin".to_string()),
+        (false, _) => Vec::new()
+    };
+    let indices = Indices::new(indices, vertices.len());
+    Ok(MeshRecord { name, vertices, indices, sub_meshes, bounds, lods, joints })
+}
+
+/// Checks every primitive has the attributes `vertex_type` is built from, tangents are generated from the normals and uvs
+fn check_attributes(primitives: &[Primitive], vertex_type: VertexType) -> Result<(), String> {
+    for (i, primitive) in primitives.iter().enumerate() {
+        let missing = if vertex_type != VertexType::Basic && primitive.uvs.is_none() {
+            "uvs"
+        } else if vertex_type != VertexType::Basic && vertex_type != VertexType::U && primitive.normals.is_none() {
+            "normals"
+        } else if vertex_type.has_joints() && primitive.joints.is_none() {
+            "joints and weights"
+        } else {
+            continue
+        };
+        return Err(format!("primitive {} has no {}, vertex type {} needs them", i, missing, vertex_type.name()))
+    }
+    Ok(())
+}
+
+/// Vertex attributes of a gltf primitive
+pub struct Primitive {
+    pub material: String,
+    pub indices: Vec<u32>,
+    pub positions: Vec<[f32;3]>,
+    pub normals: Option<Vec<[f32;3]>>,
+    pub uvs: Option<Vec<[f32;2]>>,
+    /// generated for the vertex types that have them
+    pub tangents: Option<Vec<[f32;4]>>,
+    pub joints: Option<Vec<[u16;4]>>,
+    pub weights: Option<Vec<[f32;4]>>
+}
 
-            res.0.push(joints.len()as u8);
-            for (joint_id, joint) in joints.iter().enumerate() {
-                res.append_string(joint.name().unwrap().to_string().replace('#', ""));
-                res.0.push(get_gltf_node_parent_id(&joints, &joint));
-                for parent in &joints_parents[joint_id] {
-                    res.0.push(*parent);
+fn read_primitives<'a>(meshes: impl Iterator<Item = gltf::Mesh<'a>>, buffers: &[gltf::buffer::Data], file_name: &str) -> Result<Vec<Primitive>, String> {
+    let mut res = Vec::new();
+    for mesh in meshes {
+        for primitive in mesh.primitives() {
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+            let joints: Vec<Vec<[u16;4]>> = (0..).map_while(|set| reader.read_joints(set)).map(|v| v.into_u16().collect()).collect();
+            let weights: Vec<Vec<[f32;4]>> = (0..).map_while(|set| reader.read_weights(set)).map(|v| v.into_f32().collect()).collect();
+            let influences = (!joints.is_empty()).then(|| {
+                let influences = Influences::strongest(&joints, &weights);
+                if influences.reduced > 0 {
+                    println!("Warning: {} vertices of {} have more than 4 joints, only the 4 with the largest weights are kept",
+                        influences.reduced, file_name);
                 }
-                res.0.push(255);
-                res.append_mat4x4(joints_poses[joint_id].into());
-                res.append_mat4x4(joints_poses_local[joint_id].into());
-                res.append_mat4x4(ibms[joint_id]);
+                influences
+            });
+            let (indices, positions) = match (reader.read_indices(), reader.read_positions()) {
+                (Some(indices), Some(positions)) => (indices.into_u32().collect(), positions.collect()),
+                _ => return Err(format!("a primitive of mesh {} has no indices or no positions", mesh.index()))
+            };
+            res.push(Primitive {
+                material: material_name(&primitive.material(), file_name),
+                indices,
+                positions,
+                normals: reader.read_normals().map(|v| v.collect()),
+                uvs: reader.read_tex_coords(0).map(|v| v.into_f32().collect()),
+                tangents: None,
+                joints: influences.as_ref().map(|influences| influences.joints.clone()),
+                weights: influences.map(|influences| influences.weights)
+            });
+        }
+    }
+    Ok(res)
+}
+
+/// Joints and weights of the vertices of a skinned primitive
+struct Influences {
+    joints: Vec<[u16;4]>,
+    weights: Vec<[f32;4]>,
+    /// vertices that had more than 4 joints
+    reduced: usize
+}
+impl Influences {
+    /// The 4 joints with the largest weights of every vertex, from all the joint sets, their weights normalized
+    fn strongest(joints: &[Vec<[u16;4]>], weights: &[Vec<[f32;4]>]) -> Self {
+        let len = joints[0].len();
+        let mut res = Self { joints: Vec::with_capacity(len), weights: Vec::with_capacity(len), reduced: 0 };
+        for i in 0..len {
+            let mut influences: Vec<(u16, f32)> = joints.iter().zip(weights.iter())
+                .flat_map(|(joints, weights)| joints[i].into_iter().zip(weights[i]))
+                .filter(|(_, weight)| *weight > 0.)
+                .collect();
+            influences.sort_by(|a, b| b.1.total_cmp(&a.1));
+            if influences.len() > 4 {
+                influences.truncate(4);
+                res.reduced += 1;
+            }
+            let sum: f32 = influences.iter().map(|(_, weight)| weight).sum();
+            let (mut vertex_joints, mut vertex_weights) = ([0;4], [0.;4]);
+            for (j, (joint, weight)) in influences.into_iter().enumerate() {
+                vertex_joints[j] = joint;
+                vertex_weights[j] = weight / sum;
             }
+            res.joints.push(vertex_joints);
+            res.weights.push(vertex_weights);
         }
-        VertexType::Basic | VertexType::U | VertexType::NU =>  {}
+        res
     }
-    res.append_bytes(b"END");
+}
 
-    println!("gltf mesh: {}, compiled in: {:.2} sec", path.display(), (Instant::now() - start).as_secs_f64());
-    res.0
+/// Scale and up axis conversion of the config, applied on top of the file's own transforms
+fn root_transform(conf: &Config) -> Matrix4<f32> {
+    let up = match conf.up_axis {
+        UpAxis::Y => Matrix4::identity(),
+        UpAxis::Z => Matrix4::from_angle_x(Deg(-90.))
+    };
+    Matrix4::from_scale(conf.scale) * up
 }
 
+fn transform_primitives(primitives: &mut [Primitive], root: Matrix4<f32>) {
+    if root == Matrix4::identity() { return }
+    // the root transform is a rotation and a uniform scale, normals only need the rotation
+    let rotation = Matrix3::from_cols(root.x.truncate(), root.y.truncate(), root.z.truncate());
+    for primitive in primitives.iter_mut() {
+        for position in primitive.positions.iter_mut() {
+            *position = (root * Vector3::from(*position).extend(1.)).truncate().into();
+        }
+        if let Some(normals) = primitive.normals.as_mut() {
+            for normal in normals.iter_mut() {
+                *normal = (rotation * Vector3::from(*normal)).normalize().into();
+            }
+        }
+    }
+}
+
+/// Builds the vertices of every primitive, identical vertices are stored once and shared through the indices
 #[inline]
-fn write_vertices(
-    res: &mut Writer,
-    conf: &Config,
-    gltf: &gltf::Document,
-    buffers: &Vec<gltf::buffer::Data>,
-    f: fn(&mut Writer, Vec<u32>, Vec<[f32;3]>, Option<Vec<[f32;3]>>, Option<Vec<[f32;2]>>, Option<Vec<[u16;4]>>, Option<Vec<[f32;4]>>)
-) {
-    let mut vertices = 0u32;
-    match conf.vertex_type {
-        VertexType::Basic => res.append_bytes(b"Basic#"),
-        VertexType::NUS => res.append_bytes(b"NUS#"),
-        VertexType::NU => res.append_bytes(b"NU#"),
-        VertexType::U => res.append_bytes(b"U#")
+fn collect_vertices<V: bytemuck::Pod>(
+    primitives: &[Primitive],
+    vertices_type: fn(Slice<V>) -> Vertices,
+    f: fn(&Primitive, usize) -> V
+) -> (Vertices, Vec<u32>) {
+    let mut vertices = Vec::new();
+    let mut indices = Vec::with_capacity(primitives.iter().map(|p| p.indices.len()).sum());
+    let mut unique: HashMap<Vec<u8>, u32> = HashMap::new();
+    for primitive in primitives {
+        for idx in primitive.indices.iter() {
+            let vertex = f(primitive, *idx as usize);
+            let id = *unique.entry(bytemuck::bytes_of(&vertex).to_vec()).or_insert_with(|| {
+                vertices.push(vertex);
+                vertices.len() as u32 - 1
+            });
+            indices.push(id);
+        }
     }
-    for mesh in gltf.meshes() {
-        for primitive in mesh.primitives() {
-            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
-            let indices: Vec<u32> = reader.read_indices().unwrap().into_u32().collect();
-            vertices += indices.len() as u32;
+    (vertices_type(vertices.into()), indices)
+}
+
+/// Name of the material record of a gltf material, empty for the default material.
+/// Prefixed by the file name, exporters give the same default names to the materials of every file
+fn material_name(material: &gltf::Material, file_name: &str) -> String {
+    match (material.index(), material.name()) {
+        (None, _) => String::new(),
+        (Some(_), Some(name)) => format!("{}_{}", file_name, name),
+        (Some(index), None) => format!("{}_{}", file_name, index)
+    }
+}
+
+fn material_record(material: &gltf::Material, file_name: &str, shader: VertexType, path: &Path) -> MaterialRecord {
+    let pbr = material.pbr_metallic_roughness();
+    MaterialRecord {
+        name: material_name(material, file_name),
+        shader,
+        base_color_factor: pbr.base_color_factor(),
+        base_color_texture: pbr.base_color_texture().and_then(|info| texture_name(&info.texture(), path)),
+        normal_texture: material.normal_texture().and_then(|info| texture_name(&info.texture(), path)),
+        metallic_roughness_texture: pbr.metallic_roughness_texture().and_then(|info| texture_name(&info.texture(), path)),
+        alpha_mode: match material.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => AlphaMode::Mask(material.alpha_cutoff().unwrap_or(0.5)),
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend
+        },
+        double_sided: material.double_sided()
+    }
+}
+
+/// Texture records are named after their image file, images embedded in the gltf are not compiled
+fn texture_name(texture: &gltf::Texture, path: &Path) -> Option<String> {
+    match texture.source().source() {
+        gltf::image::Source::Uri { uri, .. } => Path::new(uri).file_stem().map(|stem| stem.to_string_lossy().to_string()),
+        gltf::image::Source::View { .. } => {
+            println!("Warning: gltf mesh: {}, embedded image {} is not supported, save it next to the gltf file",
+                path.display(), texture.source().index());
+            None
         }
     }
-    res.append_u32(vertices);
-    for mesh in gltf.meshes() {
-        for primitive in mesh.primitives() {
-            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
-            let positions: Vec<[f32;3]> = reader.read_positions().unwrap().collect();
-            let normals: Option<Vec<[f32;3]>> = match reader.read_normals() {
-                Some(v) => Some(v.collect()), None => None };
-            let uvs: Option<Vec<[f32;2]>> = match reader.read_tex_coords(0) {
-                Some(v) => Some(v.into_f32().collect()), None => None };
-            let joints: Option<Vec<[u16;4]>> = match reader.read_joints(0) {
-                Some(v) => Some(v.into_u16().collect()), None => None };
-            let weights: Option<Vec<[f32;4]>> = match reader.read_weights(0) {
-                Some(v) => Some(v.into_f32().collect()), None => None };
-            let indices: Vec<u32> = reader.read_indices().unwrap().into_u32().collect();
-            f(res, indices, positions, normals, uvs, joints, weights);
+}
+
+/// Joints of a skin, moved by the root transform so they still match the transformed vertices.
+/// The bounds of a joint hold the vertices it has a weight on, in the space of the joint.
+/// Fails when the skin has more joints than the game can animate or a vertex uses a joint the skin does not have
+fn read_joints(skin: &gltf::Skin, buffers: &[gltf::buffer::Data], root: Matrix4<f32>, primitives: &[Primitive]) -> Result<Vec<JointRecord>, String> {
+    let reader = skin.reader(|buffer| Some(&buffers[buffer.index()]));
+    let root_inverse = root.invert().unwrap();
+    let joints: Vec<gltf::Node> = skin.joints().collect();
+    if joints.len() > MAX_JOINTS {
+        return Err(format!("skin has {} joints, the game can not animate more than {}", joints.len(), MAX_JOINTS))
+    }
+    // without inverse bind matrices every joint is bound at the origin
+    let ibms: Vec<[[f32; 4]; 4]> = match reader.read_inverse_bind_matrices() {
+        Some(v) => v.map(|ibm| (Matrix4::from(ibm) * root_inverse).into()).collect(),
+        None => vec![root_inverse.into(); joints.len()]
+    };
+    if ibms.len() < joints.len() {
+        return Err(format!("skin has {} joints but {} inverse bind matrices", joints.len(), ibms.len()))
+    }
+    let parents_ids = joints_parents(&joints);
+
+    let joints_poses: Vec<Matrix4<f32>> = ibms.iter().take(joints.len())
+        .map(|ibm| Matrix4::from(*ibm).invert().unwrap())
+        .collect();
+    let mut joints_points = vec![Vec::new(); joints.len()];
+    for primitive in primitives {
+        let (vertex_joints, weights) = match (primitive.joints.as_ref(), primitive.weights.as_ref()) {
+            (Some(joints), Some(weights)) => (joints, weights),
+            _ => continue
+        };
+        for i in primitive.indices.iter().map(|i| *i as usize) {
+            for (joint, weight) in vertex_joints[i].iter().zip(weights[i].iter()) {
+                if *weight == 0. { continue }
+                if *joint as usize >= joints.len() {
+                    return Err(format!("a vertex uses joint {}, but the skin has {} joints", joint, joints.len()))
+                }
+                joints_points[*joint as usize].push(transform_point(ibms[*joint as usize], primitive.positions[i]));
+            }
         }
     }
+    let mut res = Vec::with_capacity(joints.len());
+    for (joint_id, joint) in joints.iter().enumerate() {
+        let mut tpose_local = joints_poses[joint_id];
+        let mut parent = parents_ids[joint_id];
+        if parent != NO_JOINT {
+            tpose_local = joints_poses[joint_id] * Matrix4::from(ibms[parent as usize])
+        }
+        let mut parents = Vec::new();
+        while parent != NO_JOINT {
+            parents.push(parent);
+            parent = parents_ids[parent as usize];
+        }
+        parents.reverse();
+        res.push(JointRecord {
+            name: match joint.name() {
+                Some(v) => v.to_string(),
+                None => return Err(format!("joint node {} has no name, animations find joints by name", joint.index()))
+            },
+            parent: parents_ids[joint_id],
+            parents,
+            tpose: joints_poses[joint_id].into(),
+            tpose_local: tpose_local.into(),
+            ibm: ibms[joint_id],
+            bounds: Bounds::from_points(joints_points[joint_id].iter().copied())
+        });
+    }
+    Ok(res)
 }
 
-fn get_gltf_node_parent_id(joints: &Vec<gltf::Node>, j: &gltf::Node) -> u8 {
+/// Id of the parent joint of every joint, `NO_JOINT` for the roots of the skin
+fn joints_parents(joints: &[gltf::Node]) -> Vec<u16> {
+    let ids: HashMap<usize, u16> = joints.iter().enumerate().map(|(id, joint)| (joint.index(), id as u16)).collect();
+    let mut res = vec![NO_JOINT; joints.len()];
     for (parent_id, joint) in joints.iter().enumerate() {
         for child in joint.children() {
-            if child.index() == j.index() {
-                return parent_id as u8
+            if let Some(id) = ids.get(&child.index()) {
+                res[*id as usize] = parent_id as u16;
             }
         }
     }
-    255
-}
\ No newline at end of file
+    res
+}
+
+/// Compiles every animation of a gltf file into one `A` record each.
+/// Each channel is sampled at the `FramesPerSecond` of the config and every joint of the file's skin is written
+/// as its armature space translation, euler rotation and scale, the same layout compile.py writes.
+pub fn animations(path: impl AsRef<Path>) -> Result<Vec<Vec<u8>>, String> {
+    let path = path.as_ref();
+    let mut records = Vec::new();
+    let conf = match Config::new(path) { Ok(v)=>v, Err(e) => return Err(e.to_string()) };
+    let root = root_transform(&conf);
+
+    let (gltf, buffers, _) = match gltf::import(path) { Ok(v)=>v, Err(e) => return Err(e.to_string()) };
+    // the joints are written in the order of the skin, a mesh bound to another skin would get the wrong ones
+    let skin = match (gltf.skins().next(), gltf.skins().len()) {
+        (Some(v), 1) => v,
+        (Some(_), len) => return Err(format!("the file has {} skins, an animation file can only have the skin it animates", len)),
+        (None, _) => return Err("the file has no skin to animate".to_string())
+    };
+    let joints: Vec<usize> = skin.joints().map(|joint| joint.index()).collect();
+    if joints.len() > MAX_JOINTS {
+        return Err(format!("skin has {} joints, the game can not animate more than {}", joints.len(), MAX_JOINTS))
+    }
+
+    let nodes: Vec<gltf::Node> = gltf.nodes().collect();
+    let mut nodes_parents = vec![None; nodes.len()];
+    for node in nodes.iter() {
+        for child in node.children() {
+            nodes_parents[child.index()] = Some(node.index());
+        }
+    }
+
+    let animations_len = gltf.animations().len();
+    let file_name = conf.name.clone().unwrap_or_else(|| path.with_extension("").file_name().unwrap().to_string_lossy().to_string());
+    for animation in gltf.animations() {
+        let start = Instant::now();
+        let name = if animations_len == 1 {
+            file_name.clone()
+        } else {
+            match animation.name() {
+                Some(v) => v.to_string(),
+                None => format!("{}_{}", file_name, animation.index())
+            }
+        };
+
+        let mut tracks: Vec<NodeTracks> = nodes.iter().map(|node| {
+            let (translation, rotation, scale) = node.transform().decomposed();
+            NodeTracks {
+                translation: Track::Constant(translation.into()),
+                rotation: Track::Constant(Quaternion::new(rotation[3], rotation[0], rotation[1], rotation[2])),
+                scale: Track::Constant(scale.into())
+            }
+        }).collect();
+        let mut duration = 0f32;
+        for channel in animation.channels() {
+            let reader = channel.reader(|buffer| Some(&buffers[buffer.index()]));
+            let (times, outputs): (Vec<f32>, _) = match (reader.read_inputs(), reader.read_outputs()) {
+                (Some(inputs), Some(outputs)) => (inputs.collect(), outputs),
+                _ => return Err(format!("a channel of animation {} has no keyframes", animation.index()))
+            };
+            duration = duration.max(*times.last().unwrap_or(&0.));
+            let interpolation = channel.sampler().interpolation();
+            let node_tracks = &mut tracks[channel.target().node().index()];
+            match outputs {
+                ReadOutputs::Translations(v) => node_tracks.translation =
+                    Track::keyframes(times, interpolation, v.map(Vector3::from).collect(), animation.index())?,
+                ReadOutputs::Rotations(v) => node_tracks.rotation =
+                    Track::keyframes(times, interpolation, v.into_f32().map(|r| Quaternion::new(r[3], r[0], r[1], r[2])).collect(), animation.index())?,
+                ReadOutputs::Scales(v) => node_tracks.scale =
+                    Track::keyframes(times, interpolation, v.map(Vector3::from).collect(), animation.index())?,
+                ReadOutputs::MorphTargetWeights(_) => {}
+            }
+        }
+
+        let frames = (duration * conf.frames_per_second).round() as u32 + 1;
+        let mut poses = Vec::with_capacity(frames as usize);
+        for frame in 0..frames {
+            let time = frame as f32 / conf.frames_per_second;
+            let locals: Vec<Matrix4<f32>> = tracks.iter().map(|node_tracks| {
+                Matrix4::from_translation(node_tracks.translation.sample(time)) *
+                Matrix4::from(node_tracks.rotation.sample(time).normalize()) *
+                {
+                    let scale = node_tracks.scale.sample(time);
+                    Matrix4::from_nonuniform_scale(scale.x, scale.y, scale.z)
+                }
+            }).collect();
+            poses.push(joints.iter().map(|joint| {
+                let mut global = locals[*joint];
+                let mut parent = nodes_parents[*joint];
+                while let Some(node) = parent {
+                    global = locals[node] * global;
+                    parent = nodes_parents[node];
+                }
+                let global = root * global;
+                let (rotation, scale) = decompose_rotation_scale(global);
+                JointPose { translation: global.w.truncate().into(), rotation, scale }
+            }).collect());
+        }
+        records.push(AnimationRecord { name: name.clone(), joints_length: joints.len() as u16, frames: poses }.encode());
+
+        println!("animation: {} ({}), compiled in: {:.2} sec", path.display(), name, (Instant::now() - start).as_secs_f64());
+    }
+    Ok(records)
+}
+
+struct NodeTracks {
+    translation: Track<Vector3<f32>>,
+    rotation: Track<Quaternion<f32>>,
+    scale: Track<Vector3<f32>>
+}
+
+enum Track<T> {
+    Constant(T),
+    Keyframes {
+        times: Vec<f32>,
+        interpolation: Interpolation,
+        /// cubic spline tracks store an in tangent, a value and an out tangent per keyframe
+        values: Vec<T>
+    }
+}
+impl<T: Interpolate> Track<T> {
+    /// Fails when the sampler of a channel of `animation` has no keyframes or not a value for each of them
+    fn keyframes(times: Vec<f32>, interpolation: Interpolation, values: Vec<T>, animation: usize) -> Result<Self, String> {
+        let stride = if let Interpolation::CubicSpline = interpolation { 3 } else { 1 };
+        if times.is_empty() {
+            return Err(format!("a channel of animation {} has no keyframes", animation))
+        }
+        if values.len() != times.len() * stride {
+            return Err(format!("a channel of animation {} has {} keyframes but {} values", animation, times.len(), values.len()))
+        }
+        Ok(Track::Keyframes { times, interpolation, values })
+    }
+    fn sample(&self, time: f32) -> T {
+        let (times, interpolation, values) = match self {
+            Track::Constant(v) => return *v,
+            Track::Keyframes { times, interpolation, values } => (times, interpolation, values)
+        };
+        let stride = if let Interpolation::CubicSpline = interpolation { 3 } else { 1 };
+        let value = |key: usize| values[key * stride + stride / 2];
+        let next = times.partition_point(|t| *t <= time);
+        if next == 0 { return value(0) }
+        if next == times.len() { return value(times.len() - 1) }
+        let previous = next - 1;
+        let delta = times[next] - times[previous];
+        // keyframes out of order would divide by zero or go backwards
+        if delta <= 0. { return value(previous) }
+        let t = (time - times[previous]) / delta;
+        match interpolation {
+            Interpolation::Step => value(previous),
+            Interpolation::Linear => T::interpolate(value(previous), value(next), t),
+            Interpolation::CubicSpline => {
+                let t2 = t * t;
+                let t3 = t2 * t;
+                T::combine(&[
+                    (value(previous), 2. * t3 - 3. * t2 + 1.),
+                    (values[previous * 3 + 2], (t3 - 2. * t2 + t) * delta),
+                    (value(next), -2. * t3 + 3. * t2),
+                    (values[next * 3], (t3 - t2) * delta)
+                ])
+            }
+        }
+    }
+}
+
+trait Interpolate: Copy {
+    fn interpolate(a: Self, b: Self, t: f32) -> Self;
+    fn combine(terms: &[(Self, f32)]) -> Self;
+}
+impl Interpolate for Vector3<f32> {
+    fn interpolate(a: Self, b: Self, t: f32) -> Self {
+        a.lerp(b, t)
+    }
+    fn combine(terms: &[(Self, f32)]) -> Self {
+        terms.iter().fold(Vector3::new(0., 0., 0.), |res, (v, w)| res + v * *w)
+    }
+}
+impl Interpolate for Quaternion<f32> {
+    fn interpolate(a: Self, b: Self, t: f32) -> Self {
+        a.slerp(b, t)
+    }
+    fn combine(terms: &[(Self, f32)]) -> Self {
+        terms.iter().fold(Quaternion::new(0., 0., 0., 0.), |res, (v, w)| res + v * *w).normalize()
+    }
+}
+
+/// Splits the upper 3x3 of a matrix into an euler rotation (applied as z * y * x) and a scale
+fn decompose_rotation_scale(m: Matrix4<f32>) -> ([f32;3], [f32;3]) {
+    let mut r = Matrix3::from_cols(m.x.truncate(), m.y.truncate(), m.z.truncate());
+    let scale = [r.x.magnitude(), r.y.magnitude(), r.z.magnitude()];
+    r.x /= scale[0];
+    r.y /= scale[1];
+    r.z /= scale[2];
+    let sin_y = -r.x.z;
+    let rotation = if sin_y.abs() < 0.9999 {
+        [r.y.z.atan2(r.z.z), sin_y.asin(), r.x.y.atan2(r.x.x)]
+    } else {
+        [0., sin_y.signum() * std::f32::consts::FRAC_PI_2, (-r.y.x).atan2(r.y.y)]
+    };
+    (rotation, scale)
+}
@@ -0,0 +1,59 @@
+use std::sync::{Mutex, atomic::{AtomicUsize, Ordering}};
+
+/// Runs `f` for every job on all cpus and returns the results in the order of `jobs`.
+/// A failed job gives back its error and the others keep running, the errors are reported with the failed files
+pub fn run<J: Sync, R: Send>(jobs: &[J], f: impl Fn(&J) -> Result<R, String> + Sync) -> Vec<Result<R, String>> {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<Result<R, String>>>> = Mutex::new((0..jobs.len()).map(|_| None).collect());
+
+    std::thread::scope(|scope| {
+        for _ in 0..num_cpus::get().min(jobs.len()) {
+            scope.spawn(|| {
+                loop {
+                    let id = next.fetch_add(1, Ordering::SeqCst);
+                    let job = match jobs.get(id) {
+                        Some(v) => v,
+                        None => break
+                    };
+                    let res = f(job);
+                    results.lock().unwrap()[id] = Some(res);
+                }
+            });
+        }
+    });
+
+    results.into_inner().unwrap().into_iter().map(|res| res.unwrap()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run;
+
+    #[test]
+    fn results_keep_the_order_of_the_jobs() {
+        let jobs: Vec<u64> = (0..64).collect();
+        // later jobs finish first
+        let results = run(&jobs, |job| {
+            std::thread::sleep(std::time::Duration::from_micros(64 - job));
+            Ok(job * 2)
+        });
+        assert_eq!(results, jobs.iter().map(|job| Ok(job * 2)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn failures_are_collected_and_the_other_jobs_still_run() {
+        let jobs: Vec<u32> = (0..10).collect();
+        let results = run(&jobs, |job| if job % 3 == 0 { Err(format!("job {} failed", job)) } else { Ok(*job) });
+        for (job, res) in jobs.iter().zip(results) {
+            match res {
+                Ok(v) => assert_eq!(v, *job),
+                Err(e) => assert_eq!(e, format!("job {} failed", job))
+            }
+        }
+    }
+
+    #[test]
+    fn no_jobs() {
+        assert!(run(&[] as &[u32], |job| Ok(*job)).is_empty());
+    }
+}
@@ -0,0 +1,37 @@
+use pack::{Vertices, SubMeshRecord, LodRecord};
+
+use crate::config::Config;
+
+/// Simplifies every sub-mesh `conf.lods` times, the indices of each level are appended to `indices`.
+/// The chain stops at the first level that would keep most of the previous one's triangles
+pub fn generate(vertices: &Vertices, indices: &mut Vec<u32>, sub_meshes: &[SubMeshRecord], conf: &Config) -> Result<Vec<LodRecord>, String> {
+    if conf.lods == 0 || vertices.is_empty() { return Ok(Vec::new()) }
+    // every vertex type starts with its position
+    let data = vertices.bytes();
+    let positions = match meshopt::VertexDataAdapter::new(data, data.len() / vertices.len(), 0) {
+        Ok(v) => v,
+        Err(e) => return Err(format!("can not simplify the mesh, {}", e))
+    };
+    let mut lods = Vec::with_capacity(conf.lods as usize);
+    let mut previous = indices.len();
+    for level in 1..=conf.lods as i32 {
+        let ratio = conf.lod_ratio.powi(level);
+        let levels: Vec<Vec<u32>> = sub_meshes.iter().map(|sub_mesh| {
+            let start = sub_mesh.indices_start as usize;
+            let source = &indices[start..start + sub_mesh.indices_len as usize];
+            let target = (source.len() as f32 * ratio) as usize / 3 * 3;
+            meshopt::simplify(source, &positions, target, conf.lod_error)
+        }).collect();
+        let len: usize = levels.iter().map(Vec::len).sum();
+        if len as f32 > previous as f32 * 0.9 { break }
+        previous = len;
+        let mut lod = LodRecord { distance: conf.lod_distance * 2f32.powi(level - 1), indices: Vec::with_capacity(levels.len()) };
+        for level in levels {
+            let start = indices.len() as u32;
+            indices.extend_from_slice(&level);
+            lod.indices.push(start..indices.len() as u32);
+        }
+        lods.push(lod);
+    }
+    Ok(lods)
+}
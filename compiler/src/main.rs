@@ -1,63 +1,277 @@
-use std::{path::{Path, PathBuf}, sync::{Mutex, Arc, atomic::AtomicU16}, time::{Instant, Duration}, io::Write};
+use std::{path::{Path, PathBuf}, time::Instant, io::Write, collections::{BTreeMap, HashMap, HashSet}};
 
-mod writer;
 mod config;
 mod gltf;
+mod lod;
+mod tangents;
 mod texture;
 mod blender;
+mod cache;
+mod jobs;
+mod cli;
+mod watch;
 
 fn main() {
+    let args = match cli::Args::parse(std::env::args().skip(1)) {
+        Ok(v) => v,
+        Err(e) => {
+            println!("Error: {}\n\n{}", e, cli::USAGE);
+            std::process::exit(2)
+        }
+    };
+    if args.help {
+        return println!("{}", cli::USAGE)
+    }
+    if !args.input.is_dir() {
+        println!("Error: input directory {} not found", args.input.display());
+        std::process::exit(2)
+    }
+    if args.list {
+        return list(&args)
+    }
+    let res = compile(&args);
+    if let Err(e) = res.as_ref() {
+        println!("Error: {}", e);
+    }
+    if args.watch {
+        watch::run(&args, res.ok())
+    }
+    match res {
+        Ok(Compiled { failed: 0, .. }) => {},
+        _ => std::process::exit(1)
+    }
+}
+
+/// One unit of work of the compiler, with the pack its records go to
+enum Job {
+    /// a gltf, glb or image file
+    File { file: PathBuf, pack: PathBuf },
+    /// every fbx animation of a pack, blender compiles them in one run
+    Blender { files: Vec<PathBuf>, pack: PathBuf }
+}
+impl Job {
+    /// Jobs of the source files that pass the include and exclude globs, in path order
+    fn all(args: &cli::Args, files: &[PathBuf]) -> Vec<Self> {
+        let mut res = Vec::new();
+        let mut fbx: BTreeMap<PathBuf, Vec<PathBuf>> = BTreeMap::new();
+        for file in files.iter().filter(|file| args.filter(file)) {
+            let pack = || match config::Config::new(file).ok().and_then(|conf| conf.pack) {
+                Some(name) => args.output.with_file_name(format!("{}.bin", name)),
+                None => args.output.clone()
+            };
+            match extension(file) {
+                "gltf" | "glb" | "png" | "jpeg" | "jpg" => res.push(Self::File { file: file.clone(), pack: pack() }),
+                // fbx animations can only be compiled by blender
+                "fbx" if is_animation(&args.input, file) => fbx.entry(pack()).or_default().push(file.clone()),
+                _ => {}
+            }
+        }
+        res.extend(fbx.into_iter().map(|(pack, files)| Self::Blender { files, pack }));
+        res
+    }
+    fn pack(&self) -> &Path {
+        match self {
+            Self::File { pack, .. } | Self::Blender { pack, .. } => pack
+        }
+    }
+    /// The path its records are cached by and the other files they are built from
+    fn sources(&self, args: &cli::Args) -> (PathBuf, Vec<PathBuf>) {
+        match self {
+            Self::File { file, .. } if extension(file) == "gltf" || extension(file) == "glb" => (file.clone(), gltf::dependencies(file)),
+            Self::File { file, .. } => (file.clone(), Vec::new()),
+            Self::Blender { files, pack } => (
+                args.input.join("animations").join(pack.file_name().unwrap()),
+                files.clone()
+            )
+        }
+    }
+    fn kind(&self, args: &cli::Args) -> &'static str {
+        match self {
+            Self::File { file, .. } if extension(file) == "gltf" || extension(file) == "glb" =>
+                if is_animation(&args.input, file) { "gltf animations" } else if is_scene(file) { "gltf scene" } else { "gltf mesh" },
+            Self::File { .. } => "texture",
+            Self::Blender { .. } => "fbx animations"
+        }
+    }
+    /// The records of the job, or why they could not be compiled
+    fn compile(&self, args: &cli::Args) -> Result<Vec<Vec<u8>>, String> {
+        match self {
+            Self::File { file, .. } => match extension(file) {
+                "gltf" | "glb" if is_animation(&args.input, file) => gltf::animations(file),
+                "gltf" | "glb" if is_scene(file) => gltf::scene(file),
+                "gltf" | "glb" => gltf::file(file),
+                _ => texture::file(file).map(|record| vec![record])
+            },
+            Self::Blender { files, .. } => {
+                let mut animations = blender::run_blender_compiler(files)?;
+                animations.sort_by_key(|record| record_name(record).to_vec());
+                Ok(animations)
+            }
+        }
+    }
+}
+
+/// Records of every pack a compile wrote
+pub struct Compiled {
+    pub packs: BTreeMap<PathBuf, Vec<Vec<u8>>>,
+    pub failed: usize
+}
+
+/// Compiles every source file and writes the packs, the output pack is always written, even when empty.
+/// Fails when a compile.conf has errors or a pack can not be written
+fn compile(args: &cli::Args) -> Result<Compiled, String> {
     println!("Compiling ...");
     let start = Instant::now();
-    std::fs::create_dir("./assets/").err();
-
-    let files = Arc::new(Mutex::new(Vec::new()));
-    read_dir("./assets/", files.clone());
-
-    let result = Arc::new(Mutex::new(Vec::new()));
-    let threads = Arc::new(AtomicU16::new(0));
-    for _ in 0..num_cpus::get() {
-        let files = files.clone();
-        let result = result.clone();
-        let threads = threads.clone();
-        threads.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-        std::thread::spawn(move || {
-            loop {
-                if files.lock().unwrap().len() == 0 { break }
-                let file = files.lock().unwrap().remove(0);
-                let ext = file.extension().unwrap().clone();
-                match ext.to_str().unwrap() {
-                    "gltf" | "glb" => {
-                        let v = gltf::file(file);
-                        result.lock().unwrap().push(v);
-                    },
-                    "png" | "jpeg" | "jpg" => {
-                        let v = texture::file(file);
-                        result.lock().unwrap().push(v);
-                    },
-                    _ => {}
-                }
-            }
-            threads.fetch_sub(1, std::sync::atomic::Ordering::SeqCst);
-        });
+
+    let files = source_files(args);
+    if !check_configs(&files) {
+        return Err("nothing was compiled, fix the compile.conf errors first".to_string())
+    }
+
+    let cache = cache::Cache::open(&args.input);
+    let jobs = Job::all(args, &files);
+    let results = jobs::run(&jobs, |job| {
+        let (source, dependencies) = job.sources(args);
+        cache.get_or_compile(&source, &dependencies, || {
+            let compression = config::Config::new(&source).map(|conf| conf.compression).unwrap_or_default();
+            job.compile(args).map(|records| records.iter().map(|record| pack::compress(record, compression)).collect())
+        })
+    });
+
+    let mut packs = BTreeMap::from([(args.output.clone(), Vec::new())]);
+    let mut failed = Vec::new();
+    let mut owners = HashMap::new();
+    for (job, res) in jobs.iter().zip(results) {
+        let source = job.sources(args).0;
+        match res.and_then(|records| claim_names(&mut owners, job.pack(), &source, records)) {
+            Ok(v) => packs.entry(job.pack().to_path_buf()).or_default().extend(v),
+            Err(e) => failed.push(format!("{}: {}", source.display(), e))
+        }
+    }
+    cache.save();
+
+    for (path, records) in packs.iter() {
+        write_pack(path, records)?
+    }
+    let compressed: Vec<String> = packs.values().flatten().filter_map(|record| match pack::compression(record) {
+        Some((compression, len)) if compression != pack::Compression::None => Some(format!("\t{} ({}): {} -> {}, {:.1}x",
+            String::from_utf8_lossy(record_name(record)), compression.name(),
+            format_size(len as u64), format_size(record.len() as u64), len as f64 / record.len() as f64)),
+        _ => None
+    }).collect();
+    if !compressed.is_empty() {
+        println!("Compressed assets:\n{}", compressed.join("\n"));
+    }
+    if packs.len() > 1 {
+        println!("Packs: {}", packs.iter().map(|(path, records)| format!("{} ({} assets)", path.display(), records.len())).collect::<Vec<_>>().join(", "));
+    }
+
+    if !failed.is_empty() {
+        println!("Failed to compile {} files, they are missing from the packs:", failed.len());
+        for v in failed.iter() {
+            println!("\t{}", v)
+        }
+    } else {
+        println!("All assets compiled in: {:.2} sec", (Instant::now() - start).as_secs_f64());
+    }
+    Ok(Compiled { packs, failed: failed.len() })
+}
+
+/// Writes a pack next to its path and renames it over the pack,
+/// a running game reloads a pack as soon as it changes, it must never see it half written
+fn write_pack(path: &Path, records: &[Vec<u8>]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).ok();
     }
-    while threads.load(std::sync::atomic::Ordering::SeqCst) > 0 {
-        std::thread::sleep(Duration::from_millis(1));
+    let temp = path.with_extension("tmp");
+    let res = std::fs::File::create(&temp).and_then(|mut file| file.write_all(&pack::Writer::pack(records)));
+    if let Err(e) = res {
+        std::fs::remove_file(&temp).ok();
+        return Err(format!("can not write {}, error: {}", temp.display(), e))
     }
+    match std::fs::rename(&temp, path) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            std::fs::remove_file(&temp).ok();
+            Err(format!("can not replace {}, error: {}", path.display(), e))
+        }
+    }
+}
 
-    let mut file = std::fs::OpenOptions::new().create(true).truncate(true).write(true).open("./assets/compiled.bin").unwrap();
-    for bytes in result.lock().unwrap().iter() {
-        file.write_all(bytes).unwrap();
+/// Registers the asset names of a job's records in its pack, fails when an earlier job or the job itself already used one,
+/// the game would only keep the last of them
+fn claim_names(
+    owners: &mut HashMap<(PathBuf, u8, Vec<u8>), PathBuf>,
+    pack: &Path,
+    source: &Path,
+    records: Vec<Vec<u8>>
+) -> Result<Vec<Vec<u8>>, String> {
+    let mut names = HashSet::new();
+    for record in records.iter() {
+        let key = (pack.to_path_buf(), record[0], record_name(record).to_vec());
+        let name = String::from_utf8_lossy(record_name(record));
+        if let Some(other) = owners.get(&key) {
+            return Err(format!("asset \"{}\" is also compiled from {}, rename one of them", name, other.display()))
+        }
+        if !names.insert(key) {
+            return Err(format!("asset \"{}\" is compiled twice from this file, rename one of them", name))
+        }
     }
-    
-    blender::run_blender_compiler();
+    owners.extend(names.into_iter().map(|key| (key, source.to_path_buf())));
+    Ok(records)
+}
 
-    println!("All assets compiled in: {:.2} sec", (Instant::now() - start).as_secs_f64());
+/// Prints the jobs a compile would run, with the size of their sources and whether their cached records are up to date
+fn list(args: &cli::Args) {
+    let files = source_files(args);
+    let config_errors = !check_configs(&files);
+    let cache = cache::Cache::open(&args.input);
+    let jobs = Job::all(args, &files);
+    let mut total = 0;
+    let mut rebuilds = 0;
+    for job in jobs.iter() {
+        let (source, dependencies) = job.sources(args);
+        let size: u64 = std::iter::once(&source).chain(dependencies.iter())
+            .filter_map(|path| std::fs::metadata(path).ok())
+            .filter(|meta| meta.is_file())
+            .map(|meta| meta.len())
+            .sum();
+        let status = match cache.check(&source, &dependencies) {
+            Some(reason) => {
+                rebuilds += 1;
+                format!("rebuild, {}", reason)
+            },
+            None => "cached".to_string()
+        };
+        total += size;
+        println!("{:>10}  {:<15}  {} -> {} ({})", format_size(size), job.kind(args), source.display(),
+            job.pack().file_name().unwrap().to_string_lossy(), status);
+    }
+    println!("{} jobs, {} of sources, {} to rebuild", jobs.len(), format_size(total), rebuilds);
+    if config_errors {
+        std::process::exit(1)
+    }
 }
-fn read_dir(
-    dir: impl AsRef<Path>,
-    files: Arc<Mutex<Vec<PathBuf>>>
-) {
+
+/// Every file inside the input directory, in path order
+fn source_files(args: &cli::Args) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    read_dir(&args.input, &mut files);
+    files.sort();
+    files
+}
+/// Prints the errors of every compile.conf, returns whether there were none
+fn check_configs(files: &[PathBuf]) -> bool {
+    let mut res = true;
+    for path in files.iter().filter(|path| path.ends_with(config::CONFIG_FILE)) {
+        for e in config::Config::check(path) {
+            println!("Error: {}", e);
+            res = false;
+        }
+    }
+    res
+}
+fn read_dir(dir: impl AsRef<Path>, files: &mut Vec<PathBuf>) {
     let dir = dir.as_ref();
     let dirs = match std::fs::read_dir(dir) {
         Ok(v) => v,
@@ -65,10 +279,58 @@ fn read_dir(
     };
     for path in dirs {
         let path = path.unwrap().path();
-        if path.is_dir() && path.file_name().unwrap() != "animations" {
-            read_dir(path, files.clone())
+        if path.is_dir() {
+            if path.file_name().is_some_and(|name| name != cache::CACHE_DIR) {
+                read_dir(path, files)
+            }
         } else if path.is_file() {
-            files.lock().unwrap().push(path.to_path_buf());
+            files.push(path.to_path_buf());
         }
     }
-}
\ No newline at end of file
+}
+/// Name bytes of a record, between its kind byte and the `#` that ends them
+pub fn record_name(record: &[u8]) -> &[u8] {
+    let end = record.iter().position(|b| *b == b'#').unwrap_or(record.len());
+    &record[1.min(end)..end]
+}
+/// Files inside an `animations` directory only contribute their animations, not their meshes
+fn is_animation(input: &Path, path: &Path) -> bool {
+    path.strip_prefix(input).unwrap_or(path).components().any(|c| c.as_os_str() == "animations")
+}
+/// Files with `Scene = true` in their compile.conf keep their nodes
+fn is_scene(path: &Path) -> bool {
+    config::Config::new(path).is_ok_and(|conf| conf.scene)
+}
+fn extension(path: &Path) -> &str {
+    path.extension().and_then(|ext| ext.to_str()).unwrap_or_default()
+}
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{} B", bytes),
+        1024..=1048575 => format!("{:.1} KB", bytes as f64 / 1024.),
+        _ => format!("{:.1} MB", bytes as f64 / 1048576.)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::Path};
+
+    #[test]
+    fn duplicate_asset_names_fail_the_later_job() {
+        let mut owners = HashMap::new();
+        let pack = Path::new("compiled.bin");
+        let record = |kind: u8, name: &str| [&[kind], name.as_bytes(), b"#\0END"].concat();
+        let a = vec![record(b'M', "ch"), record(b'P', "ch_Material")];
+        assert_eq!(super::claim_names(&mut owners, pack, Path::new("a.gltf"), a.clone()), Ok(a));
+        // another kind or another pack can use the same name
+        assert!(super::claim_names(&mut owners, pack, Path::new("ch.png"), vec![record(b'T', "ch")]).is_ok());
+        assert!(super::claim_names(&mut owners, Path::new("other.bin"), Path::new("c.gltf"), vec![record(b'M', "ch")]).is_ok());
+
+        let e = super::claim_names(&mut owners, pack, Path::new("b.gltf"), vec![record(b'M', "b"), record(b'P', "ch_Material")]).unwrap_err();
+        assert!(e.contains("ch_Material") && e.contains("a.gltf"), "{}", e);
+        // the failed job did not claim its other names
+        assert!(super::claim_names(&mut owners, pack, Path::new("d.gltf"), vec![record(b'M', "b")]).is_ok());
+        assert!(super::claim_names(&mut owners, pack, Path::new("e.gltf"), vec![record(b'M', "e"), record(b'M', "e")]).is_err());
+    }
+}
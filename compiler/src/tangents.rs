@@ -0,0 +1,50 @@
+use std::path::Path;
+
+use crate::gltf::Primitive;
+
+/// Gives every corner of the primitive a MikkTSpace tangent. Corners no longer share vertices,
+/// `collect_vertices` merges the ones that still match, tangents split the others along uv seams
+pub fn generate(primitive: &mut Primitive, path: &Path) -> Result<(), String> {
+    if primitive.normals.is_none() || primitive.uvs.is_none() {
+        return Err("the mesh needs normals and uvs to generate tangents".to_string())
+    }
+    fn unweld<T: Copy>(attribute: &mut Option<Vec<T>>, indices: &[u32]) {
+        if let Some(values) = attribute.as_mut() {
+            *values = indices.iter().map(|i| values[*i as usize]).collect()
+        }
+    }
+    let indices = std::mem::take(&mut primitive.indices);
+    primitive.positions = indices.iter().map(|i| primitive.positions[*i as usize]).collect();
+    unweld(&mut primitive.normals, &indices);
+    unweld(&mut primitive.uvs, &indices);
+    unweld(&mut primitive.joints, &indices);
+    unweld(&mut primitive.weights, &indices);
+    primitive.indices = (0..indices.len() as u32).collect();
+    primitive.tangents = Some(vec![[1., 0., 0., 1.]; indices.len()]);
+    if !bevy_mikktspace::generate_tangents(&mut Geometry(primitive)) {
+        println!("Warning: MikkTSpace can not generate the tangents of {}, they all point along x", path.display())
+    }
+    Ok(())
+}
+
+struct Geometry<'a>(&'a mut Primitive);
+impl bevy_mikktspace::Geometry for Geometry<'_> {
+    fn num_faces(&self) -> usize {
+        self.0.indices.len() / 3
+    }
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+    fn position(&self, face: usize, vert: usize) -> [f32;3] {
+        self.0.positions[face * 3 + vert]
+    }
+    fn normal(&self, face: usize, vert: usize) -> [f32;3] {
+        self.0.normals.as_ref().unwrap()[face * 3 + vert]
+    }
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32;2] {
+        self.0.uvs.as_ref().unwrap()[face * 3 + vert]
+    }
+    fn set_tangent_encoded(&mut self, tangent: [f32;4], face: usize, vert: usize) {
+        self.0.tangents.as_mut().unwrap()[face * 3 + vert] = tangent
+    }
+}
@@ -1,24 +1,84 @@
 use std::{path::Path, time::Instant};
 
-use image::GenericImageView;
+use image::{imageops::FilterType, RgbaImage};
+use pack::{TextureRecord, TextureFormat, ColorSpace, mip_levels, encode_bc1, encode_bc3};
 
-pub fn file(path: impl AsRef<Path>) -> Vec<u8> {
-    let start = Instant::now();
-    let mut res = crate::writer::Writer(Vec::new());
-    res.0.push(b'I');
+use crate::config::Config;
+
+/// Suffixes of textures holding data instead of colors, like `rock_normal.png`
+const LINEAR_SUFFIXES: [&str; 9] = ["_normal", "_n", "_orm", "_mr", "_roughness", "_metallic", "_ao", "_mask", "_height"];
 
+pub fn file(path: impl AsRef<Path>) -> Result<Vec<u8>, String> {
+    let start = Instant::now();
     let path = path.as_ref();
-    let image = image::load_from_memory(&std::fs::read(path).unwrap()).unwrap();
-    let rgb = image.to_rgb8();
-    let dimensions = image.dimensions();
-    res.append_string(path.with_extension("").file_name().unwrap().to_string_lossy().to_string());
-    res.append_u32(dimensions.0);
-    res.append_u32(dimensions.1);
-    for rgb in rgb.pixels() {
-        res.0.push(rgb[0]); res.0.push(rgb[1]); res.0.push(rgb[2]);
+    let conf = match Config::new(path) { Ok(v)=>v, Err(e) => return Err(e.to_string()) };
+    let name = conf.name.clone().unwrap_or_else(|| path.with_extension("").file_name().unwrap().to_string_lossy().to_string());
+    let data = match std::fs::read(path) { Ok(v)=>v, Err(e) => return Err(format!("can not read the file, {}", e)) };
+    let image = match image::load_from_memory(&data) { Ok(v)=>v, Err(e) => return Err(format!("can not decode the image, {}", e)) };
+    let mut channels = image.color().channel_count();
+    let image = image.to_rgba8();
+    let (width, height) = image.dimensions();
+
+    let color_space = match conf.color_space {
+        Some(v) => v,
+        None if is_linear(&name, &conf) => ColorSpace::Linear,
+        None => ColorSpace::Srgb
+    };
+    if channels == 4 && image.pixels().all(|pixel| pixel[3] == 255) {
+        channels = 3
+    }
+    if color_space == ColorSpace::Srgb && channels < 3 {
+        // grayscale colors are expanded, single channel GPU formats have no sRGB variant
+        channels += 2
+    }
+
+    let mut format = conf.texture_format;
+    if format != TextureFormat::Raw && (width % 4 != 0 || height % 4 != 0) {
+        println!("Warning: texture: {}, is {}x{}, block compression needs a multiple of 4, stored as RAW",
+            path.display(), width, height);
+        format = TextureFormat::Raw
+    }
+    if format != TextureFormat::Raw && channels == 2 {
+        println!("Warning: texture: {}, is grayscale with alpha, block compression would spread it over rgba, stored as RAW", path.display());
+        format = TextureFormat::Raw
+    }
+    if format == TextureFormat::Bc1 && channels == 4 {
+        // BC1 would drop the alpha channel
+        format = TextureFormat::Bc3
+    }
+    let mut mips = Vec::new();
+    let mut level = image;
+    let levels = if conf.mips { mip_levels(width, height) } else { 1 };
+    for _ in 0..levels {
+        let (w, h) = level.dimensions();
+        mips.push(match format {
+            TextureFormat::Raw => raw(&level, channels),
+            TextureFormat::Bc1 => encode_bc1(level.as_raw(), w, h),
+            TextureFormat::Bc3 => encode_bc3(level.as_raw(), w, h)
+        });
+        level = next_level(&level);
+    }
+    let record = TextureRecord { name, format, channels, color_space, width, height, mips };
+
+    println!("texture:   {}, compiled in: {:.2} sec", path.display(), (Instant::now() - start).as_secs_f64());
+    Ok(record.encode())
+}
+
+fn is_linear(name: &str, conf: &Config) -> bool {
+    name == "normal" || LINEAR_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) ||
+        conf.linear_textures.iter().any(|texture| texture == name)
+}
+
+/// Keeps the channels the texture uses, grayscale images have the same value in r, g and b
+fn raw(level: &RgbaImage, channels: u8) -> Vec<u8> {
+    match channels {
+        1 => level.pixels().map(|pixel| pixel[0]).collect(),
+        2 => level.pixels().flat_map(|pixel| [pixel[0], pixel[3]]).collect(),
+        _ => level.as_raw().clone()
     }
-    res.append_bytes(b"END");
+}
 
-    println!("texture:   {}, compiled in: {:.2} sec", path.clone().display(), (Instant::now() - start).as_secs_f64());
-    res.0
-}
\ No newline at end of file
+fn next_level(level: &RgbaImage) -> RgbaImage {
+    let (width, height) = level.dimensions();
+    image::imageops::resize(level, (width / 2).max(1), (height / 2).max(1), FilterType::Triangle)
+}
@@ -0,0 +1,83 @@
+use std::{path::{Path, PathBuf}, time::{Duration, SystemTime}, collections::HashMap, net::{TcpStream, SocketAddr}, io::Write};
+
+use crate::{cli::Args, Compiled};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Recompiles the packs every time a file of the input directory changes and sends the names of the rebuilt assets to a running game.
+/// `compiled` is the result of the compile before, None when it failed
+pub fn run(args: &Args, mut compiled: Option<Compiled>) -> ! {
+    println!("Watching {} for changes, Ctrl+C to stop", args.input.display());
+    let mut packs = vec![args.output.clone()];
+    if let Some(compiled) = compiled.as_ref() {
+        packs.extend(compiled.packs.keys().cloned())
+    }
+    let mut files = snapshot(args, &packs);
+    let mut changed = false;
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+        let current = snapshot(args, &packs);
+        if current != files {
+            // editors save in several steps, compile once the files stop changing
+            files = current;
+            changed = true;
+            continue
+        }
+        if !changed { continue }
+        changed = false;
+
+        let new = match crate::compile(args) {
+            Ok(v) => v,
+            Err(e) => {
+                println!("Error: {}, waiting for the next change", e);
+                continue
+            }
+        };
+        let previous: Vec<Vec<u8>> = compiled.map(|v| v.packs.into_values().flatten().collect()).unwrap_or_default();
+        let names = rebuilt(&previous, new.packs.values().flatten());
+        packs.extend(new.packs.keys().filter(|path| !packs.contains(path)).cloned().collect::<Vec<_>>());
+        files.retain(|path, _| !is_pack(path, &packs));
+        compiled = Some(new);
+        if names.is_empty() { continue }
+        match send(&names) {
+            Ok(()) => println!("Sent {} assets to the game", names.len()),
+            Err(e) => println!("Game not reached on {}, {}", pack::HOT_RELOAD_ADDRESS, e)
+        }
+    }
+}
+
+/// Every source file with its modification time and size, the packs the compiler writes are left out
+fn snapshot(args: &Args, packs: &[PathBuf]) -> HashMap<PathBuf, (Option<SystemTime>, u64)> {
+    crate::source_files(args).into_iter()
+        .filter(|path| !is_pack(path, packs))
+        .map(|path| {
+            let meta = std::fs::metadata(&path).ok();
+            let v = (meta.as_ref().and_then(|v| v.modified().ok()), meta.map(|v| v.len()).unwrap_or_default());
+            (path, v)
+        })
+        .collect()
+}
+
+fn is_pack(path: &Path, packs: &[PathBuf]) -> bool {
+    packs.iter().any(|pack| path == pack || path == pack.with_extension("tmp"))
+}
+
+/// Names of the assets that are new or differ from the previous pack
+fn rebuilt<'a>(previous: &[Vec<u8>], current: impl Iterator<Item = &'a Vec<u8>>) -> Vec<String> {
+    let previous: HashMap<(u8, &[u8]), &Vec<u8>> = previous.iter()
+        .map(|record| ((record[0], crate::record_name(record)), record))
+        .collect();
+    let mut names: Vec<String> = current
+        .filter(|record| previous.get(&(record[0], crate::record_name(record))) != Some(record))
+        .map(|record| String::from_utf8_lossy(crate::record_name(record)).to_string())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn send(names: &[String]) -> std::io::Result<()> {
+    let address: SocketAddr = pack::HOT_RELOAD_ADDRESS.parse().unwrap();
+    let mut stream = TcpStream::connect_timeout(&address, Duration::from_millis(200))?;
+    stream.write_all(names.join("\n").as_bytes())
+}
@@ -1,4 +1,6 @@
-use crate::{assets::Reader, transform::Transform};
+use pack::AnimationRecord;
+
+use crate::transform::Transform;
 
 pub struct AnimationJoint {
     pub pose: Transform
@@ -14,29 +16,21 @@ pub struct Animation {
     pub frames: Vec<AnimationFrame>
 }
 impl Animation {
-    pub fn load(reader: &mut Reader) -> Self {
-        let name = reader.read_string();
-
-        let joints_length = reader.read_u8() as usize;
-        let frames_length = reader.read_u32() as usize;
-
-        let mut frames = Vec::with_capacity(frames_length);
-        for _ in 0..frames_length {
-            let mut frame = AnimationFrame {
-                joints: Vec::with_capacity(joints_length)
-            };
-            for _ in 0..joints_length {
-                frame.joints.push(AnimationJoint {
-                    pose: Transform::read(reader)
-                })
-            }
-            frames.push(frame)
+    pub fn from_record(record: AnimationRecord) -> Self {
+        Self {
+            joints_length: record.joints_length as usize,
+            frames: record.frames.into_iter().map(|frame| AnimationFrame {
+                joints: frame.into_iter().map(|pose| AnimationJoint { pose: pose.into() }).collect()
+            }).collect(),
+            name: record.name
         }
-
-        reader.read_end(&name);
-
+    }
+    /// Animation without joints, stands for a missing one, armatures refuse to play it
+    pub fn empty(name: impl AsRef<str>) -> Self {
         Self {
-            name, frames, joints_length
+            name: name.as_ref().to_string(),
+            joints_length: 0,
+            frames: vec![AnimationFrame { joints: Vec::new() }]
         }
     }
-}
\ No newline at end of file
+}
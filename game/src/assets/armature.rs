@@ -1,11 +1,12 @@
 use std::sync::{Arc, Mutex};
 use cgmath::{Vector3, Matrix4};
+use pack::Bounds;
 use wgpu::util::DeviceExt;
 
 use crate::utils::{vec3_linear_interpolation, scale_to_mat4, rotation_to_quaternion, mat4_to_mat3};
-use super::{Mesh, Animation};
+use super::{Mesh, Animation, Assets, LoadedAssets};
 
-pub const MAX_JOINTS: usize = 128;
+pub use pack::MAX_JOINTS;
 
 #[repr(C)]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
@@ -23,7 +24,7 @@ pub struct Armature {
     rotations_binding: Mutex<ArmatureRotationsBinding>,
     poses_buffer: wgpu::Buffer,
     rotations_buffer: wgpu::Buffer,
-    mesh: Arc<Mesh>,
+    mesh: Mutex<Arc<Mesh>>,
     animation: Mutex<Option<Arc<Animation>>>,
     speed: Mutex<f32>,
     time: Mutex<f32>
@@ -70,7 +71,7 @@ impl Armature {
             bind_group,
             poses_binding: Mutex::new(poses_binding),
             rotations_binding: Mutex::new(rotations_binding),
-            mesh,
+            mesh: Mutex::new(mesh),
             animation: Mutex::new(None),
             speed: Mutex::new(0.1),
             time: Mutex::new(0.)
@@ -82,19 +83,54 @@ impl Armature {
             animation.frames.len()
         } else { 0 }
     }
+    /// Plays the animation from its first frame, an animation made for other joints is ignored
     pub fn set_animation(&self, animation: Arc<Animation>) {
-        if self.mesh.joints.len() != animation.joints_length {
-            panic!("Animation has {} joints, but mesh has {} joints", animation.joints_length, self.mesh.joints.len())
+        let joints = self.mesh.lock().unwrap().joints.len();
+        if joints != animation.joints_length {
+            warn!("Animation \"{}\" has {} joints, but its mesh has {joints}, it is not played", animation.name, animation.joints_length);
+            return
         }
         *self.animation.lock().unwrap() = Some(animation);
         *self.time.lock().unwrap() = 0.;
     }
+    /// Swaps the mesh for its hot reloaded version, an animation made for other joints is stopped
+    pub fn set_mesh(&self, mesh: Arc<Mesh>) {
+        let mut animation = self.animation.lock().unwrap();
+        if animation.as_ref().is_some_and(|animation| animation.joints_length != mesh.joints.len()) {
+            warn!("Mesh \"{}\" now has {} joints, its animation is stopped", mesh.name, mesh.joints.len());
+            *animation = None;
+        }
+        *self.mesh.lock().unwrap() = mesh;
+    }
+    /// Keeps playing the current animation from the same time when it was hot reloaded
+    pub fn reload(&self, assets: &Assets, loaded: &LoadedAssets) {
+        let mut animation = self.animation.lock().unwrap();
+        let (name, new) = match animation.as_ref() {
+            Some(v) if loaded.animations.contains(&v.name) => match assets.find_animation(&v.name) {
+                Some(new) => (v.name.clone(), new),
+                None => return
+            },
+            _ => return
+        };
+        let joints = self.mesh.lock().unwrap().joints.len();
+        if new.joints_length != joints {
+            warn!("Animation \"{name}\" now has {} joints, but its mesh has {joints}, it is stopped", new.joints_length);
+            *animation = None;
+            return
+        }
+        let mut time = self.time.lock().unwrap();
+        if *time >= new.frames.len() as f32 {
+            *time = 0.
+        }
+        *animation = Some(new);
+    }
     pub fn update(&self, queue: &wgpu::Queue) {
         if self.animation.lock().unwrap().as_ref().is_some() {
             let mut poses_binding = self.poses_binding.lock().unwrap();
             let mut rotations_binding = self.rotations_binding.lock().unwrap();
 
-            for (joint_id, joint) in self.mesh.joints.iter().enumerate() {
+            let mesh = self.mesh.lock().unwrap().clone();
+            for (joint_id, joint) in mesh.joints.iter().enumerate() {
                 let mat = self.get_joint_pose(joint_id) * joint.ibm;
                 poses_binding.mats[joint_id] = mat.into();
                 rotations_binding.mats[joint_id] = Matrix4::from(mat4_to_mat3(mat)).into();
@@ -110,6 +146,14 @@ impl Armature {
             }
         }
     }
+    /// Bounds of the mesh in the current pose, the bounds of every joint moved by its pose
+    #[allow(dead_code)]
+    pub fn bounds(&self) -> Bounds {
+        let mesh = self.mesh.lock().unwrap().clone();
+        mesh.joints.iter().enumerate()
+            .filter(|(_, joint)| !joint.bounds.is_empty())
+            .fold(Bounds::EMPTY, |res, (joint_id, joint)| res.union(&joint.bounds.transform(self.get_joint_pose(joint_id).into())))
+    }
     pub fn get_joint_pose(&self, joint_id: usize) -> Matrix4<f32> {
         if let Some(animation) = self.animation.lock().unwrap().as_ref() {
             let time = *self.time.lock().unwrap();
@@ -125,7 +169,7 @@ impl Armature {
                 Matrix4::from(rotation_to_quaternion(current_joint.rotation).nlerp(rotation_to_quaternion(next_joint.rotation), t)) *
                 scale_to_mat4(vec3_linear_interpolation(current_joint.scale, next_joint.scale, t))
             }
-        } else { self.mesh.joints[joint_id].tpose }
+        } else { self.mesh.lock().unwrap().joints[joint_id].tpose }
     }
     pub fn get_joint_translation(&self, joint_id: usize) -> Vector3<f32> {
         if let Some(animation) = self.animation.lock().unwrap().as_ref() {
@@ -1,85 +1,327 @@
-use std::{sync::{Arc, Mutex}, path::Path, time::Instant};
+use std::{sync::{Arc, Mutex}, path::{Path, PathBuf}, time::Instant};
 
-use crate::{context::Context, assets::Reader};
+use crate::{context::Context, assets::{AssetError, Record}, shaders::Material};
 
-use super::{Mesh, Texture, Animation};
+use super::{Mesh, Texture, Animation, PbrMaterial, Scene, VertexType, PackLoad, LoadProgress, Store, MeshHandle, TextureHandle, AnimationHandle};
 
 pub struct Assets {
-    pub meshes: Mutex<Vec<Arc<Mesh>>>,
-    pub textures: Mutex<Vec<Arc<Texture>>>,
-    pub animations: Mutex<Vec<Arc<Animation>>>
+    pub meshes: Mutex<Store<Mesh>>,
+    pub textures: Mutex<Store<Texture>>,
+    pub animations: Mutex<Store<Animation>>,
+    pub materials: Mutex<Store<PbrMaterial>>,
+    pub scenes: Mutex<Store<Scene>>,
+    /// every loaded pack with the assets it brought, an asset stays loaded while one of its packs is
+    pub packs: Mutex<Vec<Pack>>,
+    /// packs loading in the background
+    pub loading: Mutex<Vec<Arc<PackLoad>>>
 }
+/// Bytes of records put on the GPU per frame by background loads
+const UPLOAD_BUDGET: usize = 16 << 20;
+
 impl Assets {
     pub fn new() -> Self {
         Self {
-            meshes: Mutex::new(Vec::new()),
-            textures: Mutex::new(Vec::new()),
-            animations: Mutex::new(Vec::new())
+            meshes: Mutex::new(Store::new()),
+            textures: Mutex::new(Store::new()),
+            animations: Mutex::new(Store::new()),
+            materials: Mutex::new(Store::new()),
+            scenes: Mutex::new(Store::new()),
+            packs: Mutex::new(Vec::new()),
+            loading: Mutex::new(Vec::new())
         }
     }
-    pub fn load(&self, c: &Context, path: impl AsRef<Path>) {
-        let start = Instant::now();
-        let mut meshes_loaded = Vec::new();
-        let mut textures_loaded = Vec::new();
-        let mut animations_loaded = Vec::new();
-
-        let mut meshes = self.meshes.lock().unwrap();
-        let mut textures = self.textures.lock().unwrap();
-        let mut animations = self.animations.lock().unwrap();
-
-        let mut reader = Reader::new(path);
-        while !reader.finished() {
-            match reader.read_u8() {
-                b'I' => {
-                    let texture = Texture::load(&c.device, &c.queue, &mut reader);
-                    textures_loaded.push(texture.name.clone());
-                    textures.push(Arc::new(texture));
-                },
-                b'M' => {
-                    let mesh = Mesh::load(&c.device, &mut reader);
-                    meshes_loaded.push(mesh.name.clone());
-                    meshes.push(Arc::new(mesh));
-                },
-                b'A' => {
-                    let anim = Animation::load(&mut reader);
-                    animations_loaded.push(anim.name.clone());
-                    animations.push(Arc::new(anim));
-                },
-                asset_type => panic!("Invalid asset type: {}", asset_type)
+    /// Loads every asset of a pack, assets with corrupted records are reported and skipped.
+    /// An asset that is already loaded is replaced, objects switch to it with `Object::reload`.
+    /// Loading a pack again also releases the assets it no longer has
+    pub fn load(&self, c: &Context, path: impl AsRef<Path>) -> Result<LoadedAssets, AssetError> {
+        self.load_now(c, &PackLoad::start(path, None))
+    }
+    /// Starts loading a pack on worker threads, its assets show up as `upload_loading` puts them on the GPU
+    pub fn load_background(&self, path: impl AsRef<Path>) -> Arc<PackLoad> {
+        let load = PackLoad::start(path, None);
+        self.loading.lock().unwrap().push(load.clone());
+        load
+    }
+    /// Uploads what the background loads decoded, a few megabytes per call so a frame is never held for long.
+    /// Returns the loads that finished
+    pub fn upload_loading(&self, c: &Context) -> Vec<(PathBuf, Result<LoadedAssets, AssetError>)> {
+        let mut budget = UPLOAD_BUDGET;
+        let loading = self.loading.lock().unwrap().clone();
+        let mut finished = Vec::new();
+        for load in loading.iter() {
+            if let Some(res) = self.upload(c, load, &mut budget) {
+                self.loading.lock().unwrap().retain(|v| !Arc::ptr_eq(v, load));
+                finished.push((load.path.clone(), res));
+            }
+        }
+        finished
+    }
+    /// Progress of every background load
+    pub fn progress(&self) -> LoadProgress {
+        let mut res = LoadProgress::default();
+        for load in self.loading.lock().unwrap().iter() {
+            res.add(load.progress())
+        }
+        res
+    }
+    fn load_now(&self, c: &Context, load: &PackLoad) -> Result<LoadedAssets, AssetError> {
+        let mut budget = usize::MAX;
+        loop {
+            if let Some(res) = self.upload(c, load, &mut budget) {
+                return res
             }
+            load.wait()
+        }
+    }
+    /// Uploads decoded records until `budget` bytes are spent, returns the loaded assets once the pack is done
+    fn upload(&self, c: &Context, load: &PackLoad, budget: &mut usize) -> Option<Result<LoadedAssets, AssetError>> {
+        if let Some(e) = load.take_error() {
+            return Some(Err(e))
         }
+        while *budget > 0 {
+            let decoded = match load.pop() {
+                Some(v) => v,
+                None => break
+            };
+            *budget = budget.saturating_sub(decoded.length);
+            let mut loaded = LoadedAssets::default();
+            if let Err(e) = decoded.record.and_then(|record| self.insert(c, record, &mut loaded)) {
+                error!("Skipping asset \"{}\": {}", decoded.name, e)
+            }
+            load.uploaded(decoded.length, loaded);
+        }
+        let loaded = load.finish()?;
+        log::info!("Assets loaded from {}: \n\tmeshes: {:?}, \n\ttextures: {:?}, \n\tanimations: {:?}, \n\tmaterials: {:?}, \n\tscenes: {:?} \n\ttime: {:.2} sec",
+            load.path.display(),
+            loaded.meshes,
+            loaded.textures,
+            loaded.animations,
+            loaded.materials,
+            loaded.scenes,
+            (Instant::now() - load.start).as_secs_f32());
 
-        log::info!("Assets loaded: \n\tmeshes: {:?}, \n\ttextures: {:?}, \n\tanimations: {:?} \n\ttime: {:.2} sec",
-            meshes_loaded,
-            textures_loaded,
-            animations_loaded,
-            (Instant::now() - start).as_secs_f32());
-    }
-    pub fn get_mesh(&self, name: impl AsRef<str>) -> Arc<Mesh> {
-        let name = name.as_ref().to_string();
-        for v in self.meshes.lock().unwrap().iter() {
-            if v.name == name {
-                return v.clone()
+        let mut packs = self.packs.lock().unwrap();
+        let pack = match packs.iter_mut().find(|pack| pack.path == load.path) {
+            Some(v) => v,
+            None => {
+                packs.push(Pack { path: load.path.clone(), assets: LoadedAssets::default() });
+                packs.last_mut().unwrap()
+            }
+        };
+        if load.names.is_some() {
+            pack.assets.extend(loaded.clone());
+            return Some(Ok(loaded))
+        }
+        let previous = std::mem::replace(&mut pack.assets, loaded.clone());
+        let removed = LoadedAssets {
+            meshes: previous.meshes.into_iter().filter(|v| !loaded.meshes.contains(v)).collect(),
+            textures: previous.textures.into_iter().filter(|v| !loaded.textures.contains(v)).collect(),
+            animations: previous.animations.into_iter().filter(|v| !loaded.animations.contains(v)).collect(),
+            materials: previous.materials.into_iter().filter(|v| !loaded.materials.contains(v)).collect(),
+            scenes: previous.scenes.into_iter().filter(|v| !loaded.scenes.contains(v)).collect()
+        };
+        self.release(&packs, &removed);
+        Some(Ok(loaded))
+    }
+    /// Forgets the assets of a pack that no other loaded pack has.
+    /// Their GPU buffers are freed as soon as no object uses them anymore
+    pub fn unload(&self, path: impl AsRef<Path>) -> bool {
+        let mut packs = self.packs.lock().unwrap();
+        let pack = match packs.iter().position(|pack| pack.path == path.as_ref()) {
+            Some(id) => packs.remove(id),
+            None => return false
+        };
+        let in_use = self.release(&packs, &pack.assets);
+        info!("Pack {} unloaded, assets still used by objects: {in_use:?}", pack.path.display());
+        true
+    }
+    pub fn is_loaded(&self, path: impl AsRef<Path>) -> bool {
+        self.packs.lock().unwrap().iter().any(|pack| pack.path == path.as_ref())
+    }
+    /// Drops the named assets no pack of `packs` has, returns the ones objects still hold
+    fn release(&self, packs: &[Pack], assets: &LoadedAssets) -> Vec<String> {
+        fn release<T>(store: &mut Store<T>, names: &[String], kept: impl Fn(&String) -> bool, in_use: &mut Vec<String>) {
+            for name in names.iter().filter(|name| !kept(name)) {
+                if store.remove(name).is_some_and(|v| Arc::strong_count(&v) > 1) {
+                    in_use.push(name.clone())
+                }
+            }
+        }
+        let mut in_use = Vec::new();
+        release(&mut self.meshes.lock().unwrap(), &assets.meshes,
+            |v| packs.iter().any(|pack| pack.assets.meshes.contains(v)), &mut in_use);
+        release(&mut self.textures.lock().unwrap(), &assets.textures,
+            |v| packs.iter().any(|pack| pack.assets.textures.contains(v)), &mut in_use);
+        release(&mut self.animations.lock().unwrap(), &assets.animations,
+            |v| packs.iter().any(|pack| pack.assets.animations.contains(v)), &mut in_use);
+        release(&mut self.materials.lock().unwrap(), &assets.materials,
+            |v| packs.iter().any(|pack| pack.assets.materials.contains(v)), &mut in_use);
+        release(&mut self.scenes.lock().unwrap(), &assets.scenes,
+            |v| packs.iter().any(|pack| pack.assets.scenes.contains(v)), &mut in_use);
+        in_use
+    }
+    /// Loads only the records named `name`, using the table of contents to skip everything else
+    #[allow(dead_code)]
+    pub fn load_asset(&self, c: &Context, path: impl AsRef<Path>, name: impl AsRef<str>) -> Result<LoadedAssets, AssetError> {
+        let name = name.as_ref();
+        let loaded = self.load_assets(c, &path, &[name.to_string()])?;
+        if loaded.is_empty() {
+            warn!("Asset \"{name}\" not found in {}", path.as_ref().display())
+        }
+        Ok(loaded)
+    }
+    /// Loads the records of every name found in the pack, names it does not have are skipped
+    pub fn load_assets(&self, c: &Context, path: impl AsRef<Path>, names: &[String]) -> Result<LoadedAssets, AssetError> {
+        self.load_now(c, &PackLoad::start(path, Some(names.to_vec())))
+    }
+    /// Creates the GPU resources of a record, each list is only locked to swap the asset in
+    fn insert(&self, c: &Context, record: Record, loaded: &mut LoadedAssets) -> Result<(), AssetError> {
+        match record {
+            Record::Texture(record) => {
+                let texture = Texture::from_record(&c.device, &c.queue, record);
+                loaded.textures.push(texture.name.clone());
+                self.textures.lock().unwrap().insert(&texture.name.clone(), Arc::new(texture));
+            },
+            Record::Mesh(record) => {
+                let mesh = Mesh::from_record(&c.device, record)?;
+                loaded.meshes.push(mesh.name.clone());
+                self.meshes.lock().unwrap().insert(&mesh.name.clone(), Arc::new(mesh));
+            },
+            Record::Animation(record) => {
+                let anim = Animation::from_record(record);
+                loaded.animations.push(anim.name.clone());
+                self.animations.lock().unwrap().insert(&anim.name.clone(), Arc::new(anim));
+            },
+            Record::Material(record) => {
+                let material = PbrMaterial::from_record(&c.device, &c.queue, record);
+                loaded.materials.push(material.name.clone());
+                // objects built from the material sample its factor texture, they find it by name when it is reloaded
+                loaded.textures.push(material.factor_texture.name.clone());
+                self.textures.lock().unwrap().insert(&material.factor_texture.name, material.factor_texture.clone());
+                self.materials.lock().unwrap().insert(&material.name.clone(), Arc::new(material));
+            },
+            Record::Scene(record) => {
+                let scene = Scene::from_record(record);
+                loaded.scenes.push(scene.name.clone());
+                self.scenes.lock().unwrap().insert(&scene.name.clone(), Arc::new(scene));
             }
         }
-        panic!("Mesh \"{name}\" not found")
+        Ok(())
+    }
+    pub fn find_mesh(&self, name: impl AsRef<str>) -> Option<Arc<Mesh>> {
+        self.meshes.lock().unwrap().get(name.as_ref())
+    }
+    pub fn find_texture(&self, name: impl AsRef<str>) -> Option<Arc<Texture>> {
+        self.textures.lock().unwrap().get(name.as_ref())
+    }
+    pub fn find_animation(&self, name: impl AsRef<str>) -> Option<Arc<Animation>> {
+        self.animations.lock().unwrap().get(name.as_ref())
+    }
+    pub fn find_scene(&self, name: impl AsRef<str>) -> Option<Arc<Scene>> {
+        self.scenes.lock().unwrap().get(name.as_ref())
+    }
+    /// Handle of a mesh that may be loaded later, it always resolves to the mesh's current version
+    pub fn mesh_handle(&self, name: impl AsRef<str>) -> MeshHandle {
+        self.meshes.lock().unwrap().handle(name.as_ref())
+    }
+    #[allow(dead_code)]
+    pub fn texture_handle(&self, name: impl AsRef<str>) -> TextureHandle {
+        self.textures.lock().unwrap().handle(name.as_ref())
+    }
+    pub fn animation_handle(&self, name: impl AsRef<str>) -> AnimationHandle {
+        self.animations.lock().unwrap().handle(name.as_ref())
+    }
+    /// The mesh, a unit cube when it is not loaded
+    pub fn mesh(&self, c: &Context, handle: MeshHandle) -> Arc<Mesh> {
+        self.meshes.lock().unwrap().resolve(handle, "Mesh", "a cube", |name| Mesh::cube(&c.device, name, VertexType::NU))
+    }
+    /// The texture, a checkerboard when it is not loaded
+    #[allow(dead_code)]
+    pub fn texture(&self, c: &Context, handle: TextureHandle) -> Arc<Texture> {
+        self.textures.lock().unwrap().resolve(handle, "Texture", "a checkerboard", |name| Texture::checkerboard(&c.device, &c.queue, name))
+    }
+    /// The animation, one without joints that armatures do not play when it is not loaded
+    pub fn animation(&self, handle: AnimationHandle) -> Arc<Animation> {
+        self.animations.lock().unwrap().resolve(handle, "Animation", "an empty animation", |name| Animation::empty(name))
+    }
+    pub fn get_mesh(&self, c: &Context, name: impl AsRef<str>) -> Arc<Mesh> {
+        self.mesh(c, self.mesh_handle(name))
+    }
+    #[allow(dead_code)]
+    pub fn get_texture(&self, c: &Context, name: impl AsRef<str>) -> Arc<Texture> {
+        self.texture(c, self.texture_handle(name))
     }
     pub fn get_animation(&self, name: impl AsRef<str>) -> Arc<Animation> {
-        let name = name.as_ref().to_string();
-        for v in self.animations.lock().unwrap().iter() {
-            if v.name == name {
-                return v.clone()
+        self.animation(self.animation_handle(name))
+    }
+    /// Builds the shader material a glTF material was exported for,
+    /// the base color factor is used when its base color texture is missing and the vertex normals when its normal map is
+    pub fn get_material(&self, c: &Context, name: impl AsRef<str>) -> Option<Material> {
+        let name = name.as_ref();
+        let material = match self.materials.lock().unwrap().get(name) {
+            Some(v) => v,
+            None => {
+                warn!("Material \"{name}\" not found");
+                return None
             }
+        };
+        let texture = match material.base_color_texture.as_ref() {
+            Some(texture) => match self.find_texture(texture) {
+                Some(v) => v,
+                None => {
+                    warn!("Texture \"{texture}\" of material \"{name}\" not found, using its base color");
+                    material.factor_texture.clone()
+                }
+            },
+            None => material.factor_texture.clone()
+        };
+        let normal = material.normal_texture.as_ref().and_then(|normal| {
+            let res = self.find_texture(normal);
+            if res.is_none() {
+                warn!("Normal map \"{normal}\" of material \"{name}\" not found, using the vertex normals")
+            }
+            res
+        });
+        let res = Material::for_vertex_type(c, material.shader, texture, normal);
+        if res.is_none() {
+            warn!("Material \"{name}\" was exported for a {} mesh, no shader renders it", material.shader.name());
         }
-        panic!("Animation \"{name}\" not found")
+        res
     }
-    pub fn get_texture(&self, name: impl AsRef<str>) -> Arc<Texture> {
-        let name = name.as_ref().to_string();
-        for v in self.textures.lock().unwrap().iter() {
-            if v.name == name {
-                return v.clone()
+}
+
+pub struct Pack {
+    pub path: PathBuf,
+    pub assets: LoadedAssets
+}
+
+/// Names of the assets a load added or replaced
+#[derive(Default, Clone)]
+pub struct LoadedAssets {
+    pub meshes: Vec<String>,
+    pub textures: Vec<String>,
+    pub animations: Vec<String>,
+    pub materials: Vec<String>,
+    pub scenes: Vec<String>
+}
+impl LoadedAssets {
+    pub fn is_empty(&self) -> bool {
+        self.meshes.is_empty() && self.textures.is_empty() && self.animations.is_empty() && self.materials.is_empty() && self.scenes.is_empty()
+    }
+    /// Adds the names `other` has and this one does not
+    pub fn extend(&mut self, other: Self) {
+        for (list, names) in [
+            (&mut self.meshes, other.meshes),
+            (&mut self.textures, other.textures),
+            (&mut self.animations, other.animations),
+            (&mut self.materials, other.materials),
+            (&mut self.scenes, other.scenes)
+        ] {
+            for name in names {
+                if !list.contains(&name) {
+                    list.push(name)
+                }
             }
         }
-        panic!("Texture \"{name}\" not found")
     }
-}
\ No newline at end of file
+}
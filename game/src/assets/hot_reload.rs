@@ -0,0 +1,101 @@
+use std::{sync::{Arc, Mutex}, path::{Path, PathBuf}, time::{SystemTime, Duration}, net::TcpListener, io::Read};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Changes to apply before the next frame
+pub enum Reload {
+    /// the pack file was replaced, every asset is reloaded
+    Pack(PathBuf),
+    /// assets the compiler rebuilt, loaded from every watched pack that has them
+    Assets(Vec<String>)
+}
+
+/// Watches the loaded packs for changes and listens on `pack::HOT_RELOAD_ADDRESS` for the assets `compiler --watch` rebuilt
+pub struct HotReload {
+    pending: Arc<Mutex<Vec<Reload>>>,
+    packs: Arc<Mutex<Vec<WatchedPack>>>,
+    listening: Mutex<bool>
+}
+impl HotReload {
+    pub fn new() -> Self {
+        Self {
+            pending: Arc::new(Mutex::new(Vec::new())),
+            packs: Arc::new(Mutex::new(Vec::new())),
+            listening: Mutex::new(false)
+        }
+    }
+    /// Starts watching a loaded pack, the first call also starts listening for the compiler
+    pub fn watch(&self, path: impl AsRef<Path>) {
+        let path = path.as_ref().to_path_buf();
+        let packs = self.packs.clone();
+        let pending = self.pending.clone();
+        {
+            let mut packs = packs.lock().unwrap();
+            if packs.iter().any(|v| v.path == path) { return }
+            packs.push(WatchedPack { path: path.clone(), modified: modified(&path) });
+        }
+        std::thread::spawn(move || loop {
+            std::thread::sleep(POLL_INTERVAL);
+            let modified = modified(&path);
+            let mut packs = packs.lock().unwrap();
+            let known = match packs.iter_mut().find(|v| v.path == path) {
+                Some(v) => &mut v.modified,
+                None => return
+            };
+            if modified.is_some() && *known != modified {
+                *known = modified;
+                pending.lock().unwrap().push(Reload::Pack(path.clone()));
+            }
+        });
+
+        let mut listening = self.listening.lock().unwrap();
+        if *listening { return }
+        *listening = true;
+        let listener = match TcpListener::bind(pack::HOT_RELOAD_ADDRESS) {
+            Ok(v) => v,
+            Err(e) => return warn!("Hot reload can not listen on {}: {e}", pack::HOT_RELOAD_ADDRESS)
+        };
+        let packs = self.packs.clone();
+        let pending = self.pending.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut message = String::new();
+                if let Err(e) = stream.and_then(|mut stream| stream.read_to_string(&mut message)) {
+                    warn!("Hot reload message lost: {e}");
+                    continue
+                }
+                let names: Vec<String> = message.lines().map(str::to_string).filter(|name| !name.is_empty()).collect();
+                if names.is_empty() { continue }
+                // the compiler writes the pack before naming its assets, they are all that needs to be loaded
+                for pack in packs.lock().unwrap().iter_mut() {
+                    pack.modified = modified(&pack.path);
+                }
+                let mut pending = pending.lock().unwrap();
+                pending.retain(|reload| !matches!(reload, Reload::Pack(_)));
+                pending.push(Reload::Assets(names));
+            }
+        });
+    }
+    /// Stops watching a pack that was unloaded
+    pub fn unwatch(&self, path: impl AsRef<Path>) {
+        self.packs.lock().unwrap().retain(|v| v.path != path.as_ref());
+        self.pending.lock().unwrap().retain(|reload| !matches!(reload, Reload::Pack(v) if v == path.as_ref()));
+    }
+    /// Changes found since the last call
+    pub fn take(&self) -> Vec<Reload> {
+        std::mem::take(&mut self.pending.lock().unwrap())
+    }
+    pub fn packs(&self) -> Vec<PathBuf> {
+        self.packs.lock().unwrap().iter().map(|v| v.path.clone()).collect()
+    }
+}
+
+struct WatchedPack {
+    path: PathBuf,
+    /// last change that was loaded
+    modified: Option<SystemTime>
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|v| v.modified()).ok()
+}
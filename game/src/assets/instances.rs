@@ -1,12 +1,49 @@
 use std::sync::{atomic::{AtomicU32, AtomicBool}, Mutex};
 
+use cgmath::{Matrix4, Matrix3, Quaternion, InnerSpace, SquareMatrix};
 use wgpu::util::DeviceExt;
 
+use super::VertexLayout;
+
+/// Placement of one instance, the mesh is scaled, then rotated, then moved
 #[repr(C, align(8))]
 #[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
 pub struct InstanceTransform {
     pub position: [f32;3],
-    pub scale: [f32;3]
+    pub scale: [f32;3],
+    /// quaternion, x, y, z and w
+    pub rotation: [f32;4]
+}
+impl InstanceTransform {
+    pub const IDENTITY: Self = Self { position: [0.;3], scale: [1.;3], rotation: [0., 0., 0., 1.] };
+
+    pub fn mat(&self) -> Matrix4<f32> {
+        let [x, y, z, w] = self.rotation;
+        Matrix4::from_translation(self.position.into()) *
+        Matrix4::from(Quaternion::new(w, x, y, z)) *
+        Matrix4::from_nonuniform_scale(self.scale[0], self.scale[1], self.scale[2])
+    }
+    /// Splits a transform without shear, a mirrored one gets a negative x scale
+    pub fn from_mat(m: Matrix4<f32>) -> Self {
+        let mut r = Matrix3::from_cols(m.x.truncate(), m.y.truncate(), m.z.truncate());
+        let mut scale = [r.x.magnitude(), r.y.magnitude(), r.z.magnitude()];
+        if r.determinant() < 0. {
+            scale[0] = -scale[0];
+        }
+        r.x /= scale[0];
+        r.y /= scale[1];
+        r.z /= scale[2];
+        let q = Quaternion::from(r).normalize();
+        Self { position: m.w.truncate().into(), scale, rotation: [q.v.x, q.v.y, q.v.z, q.s] }
+    }
+}
+/// Locations 5, 6 and 8, `shaders::INSTANCE_WGSL` reads them
+impl VertexLayout for InstanceTransform {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Instance,
+        attributes: &wgpu::vertex_attr_array![5 => Float32x3, 6 => Float32x3, 8 => Float32x4]
+    };
 }
 
 pub struct Instances {
@@ -18,11 +55,12 @@ pub struct Instances {
 #[allow(dead_code)]
 impl Instances {
     pub fn new(device: &wgpu::Device, maximum: usize) -> Self {
-        let mut transforms = Vec::with_capacity(maximum as usize);
+        let mut transforms = Vec::with_capacity(maximum);
         for _ in 0..maximum {
             transforms.push(InstanceTransform {
                 position: [0.;3],
-                scale: [0.;3]
+                scale: [0.;3],
+                rotation: [0., 0., 0., 1.]
             });
         }
         Self {
@@ -55,6 +93,15 @@ impl Instances {
         self.buffer_len.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
         self.needs_update.store(true, std::sync::atomic::Ordering::SeqCst);
     }
+    /// Distance from `point` to the closest instance, to the origin when there are none
+    pub fn distance(&self, point: [f32;3]) -> f32 {
+        let distance = |p: [f32;3]| ((p[0] - point[0]).powi(2) + (p[1] - point[1]).powi(2) + (p[2] - point[2]).powi(2)).sqrt();
+        let transforms = self.transforms.lock().unwrap();
+        transforms[..self.get_buffer_len() as usize].iter()
+            .map(|transform| distance(transform.position))
+            .reduce(f32::min)
+            .unwrap_or_else(|| distance([0.;3]))
+    }
     pub fn update(&self, queue: &wgpu::Queue) {
         if self.needs_update.load(std::sync::atomic::Ordering::SeqCst) {
             let transforms = self.transforms.lock().unwrap();
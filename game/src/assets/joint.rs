@@ -1,6 +1,7 @@
 use cgmath::Matrix4;
-use super::{Reader, MAX_JOINTS};
+use pack::{JointRecord, Bounds};
 
+#[allow(dead_code)]
 pub struct Joint {
     pub name: String,
     pub id: usize,
@@ -10,37 +11,22 @@ pub struct Joint {
     /// nearest parent
     pub parent: usize,
     /// from farthest to nearest
-    pub parents: Vec<usize>
+    pub parents: Vec<usize>,
+    /// vertices the joint moves, in its own space, empty when it moves none
+    pub bounds: Bounds
 }
 impl Joint {
     #[inline]
-    pub fn read(reader: &mut Reader) -> Vec<Joint> {
-        let joints_length = reader.read_u8() as usize;
-        if joints_length >= MAX_JOINTS {
-            panic!("Skeleton can not have more than {} joints", MAX_JOINTS)
+    pub fn from_record(id: usize, record: &JointRecord) -> Self {
+        Self {
+            name: record.name.clone(),
+            id,
+            parent: record.parent as usize,
+            parents: record.parents.iter().map(|parent| *parent as usize).collect(),
+            tpose: record.tpose.into(),
+            tpose_local: record.tpose_local.into(),
+            ibm: record.ibm.into(),
+            bounds: record.bounds
         }
-        let mut joints = Vec::with_capacity(joints_length);
-        let mut joint_id = 0;
-        while joint_id < joints_length {
-            joints.push(Joint {
-                name: reader.read_string(),
-                id: joint_id,
-                parent: reader.read_u8() as usize,
-                parents: {
-                    let mut parents = Vec::new();
-                    let mut parent = reader.read_u8() as usize;
-                    while parent != 255 {
-                        parents.push(parent);
-                        parent = reader.read_u8() as usize;
-                    }
-                    parents
-                },
-                tpose: reader.read_mat4x4().into(),
-                tpose_local: reader.read_mat4x4().into(),
-                ibm: reader.read_mat4x4().into()
-            });
-            joint_id += 1;
-        }
-        joints
     }
-}
\ No newline at end of file
+}
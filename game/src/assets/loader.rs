@@ -0,0 +1,143 @@
+use std::{sync::{Arc, Mutex, Condvar, atomic::{AtomicUsize, Ordering}}, path::{Path, PathBuf}, collections::VecDeque, time::Instant};
+
+use super::{Reader, TocEntry, AssetError, Record, LoadedAssets};
+
+/// Bytes and assets of the packs being loaded, an asset counts as loaded once it is on the GPU
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LoadProgress {
+    pub bytes_loaded: usize,
+    pub bytes_total: usize,
+    pub assets_loaded: usize,
+    pub assets_total: usize
+}
+impl LoadProgress {
+    /// From 0 to 1, by bytes
+    pub fn fraction(&self) -> f32 {
+        if self.bytes_total == 0 { 1. } else { self.bytes_loaded as f32 / self.bytes_total as f32 }
+    }
+    pub fn add(&mut self, other: Self) {
+        self.bytes_loaded += other.bytes_loaded;
+        self.bytes_total += other.bytes_total;
+        self.assets_loaded += other.assets_loaded;
+        self.assets_total += other.assets_total;
+    }
+}
+
+/// A record decoded by a worker, waiting for its GPU upload
+pub struct Decoded {
+    pub name: String,
+    pub length: usize,
+    pub record: Result<Record, AssetError>
+}
+
+/// Pack being loaded: worker threads read and decode its records, the render thread uploads them with `Assets::upload`
+pub struct PackLoad {
+    pub path: PathBuf,
+    /// only the records with these names, the whole pack when None
+    pub names: Option<Vec<String>>,
+    pub start: Instant,
+    state: Mutex<LoadState>,
+    ready: Condvar
+}
+#[derive(Default)]
+struct LoadState {
+    decoded: VecDeque<Decoded>,
+    progress: LoadProgress,
+    /// every record was decoded or the pack could not be read
+    decoding_done: bool,
+    error: Option<AssetError>,
+    loaded: LoadedAssets
+}
+impl PackLoad {
+    /// Starts reading the pack on a new thread
+    pub fn start(path: impl AsRef<Path>, names: Option<Vec<String>>) -> Arc<Self> {
+        let load = Arc::new(Self {
+            path: path.as_ref().to_path_buf(),
+            names,
+            start: Instant::now(),
+            state: Mutex::new(LoadState::default()),
+            ready: Condvar::new()
+        });
+        let worker = load.clone();
+        std::thread::spawn(move || worker.decode());
+        load
+    }
+    fn decode(&self) {
+        let mut reader = match open(&self.path) {
+            Ok(v) => v,
+            Err(e) => return self.fail(e)
+        };
+        let toc = match reader.read_toc(&self.path) {
+            Ok(v) => v,
+            Err(e) => return self.fail(e)
+        };
+        let entries: Vec<&TocEntry> = toc.iter()
+            .filter(|entry| self.names.as_ref().is_none_or(|names| names.contains(&entry.name)))
+            .collect();
+        {
+            let mut state = self.state.lock().unwrap();
+            state.progress.bytes_total = entries.iter().map(|entry| entry.length).sum();
+            state.progress.assets_total = entries.len();
+        }
+
+        let next = AtomicUsize::new(0);
+        let workers = std::thread::available_parallelism().map_or(1, |v| v.get()).min(entries.len());
+        std::thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| while let Some(entry) = entries.get(next.fetch_add(1, Ordering::SeqCst)) {
+                    // vertices and indices of the record point into the mapped pack until they are uploaded
+                    let record = reader.clone().read_record(entry);
+                    self.state.lock().unwrap().decoded.push_back(Decoded { name: entry.name.clone(), length: entry.length, record });
+                    self.ready.notify_all();
+                });
+            }
+        });
+        self.state.lock().unwrap().decoding_done = true;
+        self.ready.notify_all();
+    }
+    fn fail(&self, error: AssetError) {
+        let mut state = self.state.lock().unwrap();
+        state.error = Some(error);
+        state.decoding_done = true;
+        self.ready.notify_all();
+    }
+    /// Blocks until a record is decoded or the loading is over
+    pub fn wait(&self) {
+        let state = self.state.lock().unwrap();
+        let _state = self.ready.wait_while(state, |state| state.decoded.is_empty() && !state.decoding_done).unwrap();
+    }
+    pub fn progress(&self) -> LoadProgress {
+        self.state.lock().unwrap().progress
+    }
+    pub(super) fn pop(&self) -> Option<Decoded> {
+        self.state.lock().unwrap().decoded.pop_front()
+    }
+    pub(super) fn take_error(&self) -> Option<AssetError> {
+        self.state.lock().unwrap().error.take()
+    }
+    /// Counts an uploaded record
+    pub(super) fn uploaded(&self, length: usize, loaded: LoadedAssets) {
+        let mut state = self.state.lock().unwrap();
+        state.progress.bytes_loaded += length;
+        state.progress.assets_loaded += 1;
+        state.loaded.extend(loaded);
+    }
+    /// Names of every uploaded asset once all of them are, None while records are left
+    pub(super) fn finish(&self) -> Option<LoadedAssets> {
+        let mut state = self.state.lock().unwrap();
+        if !state.decoding_done || !state.decoded.is_empty() { return None }
+        Some(std::mem::take(&mut state.loaded))
+    }
+}
+
+/// Maps the pack where a file can be renamed over while it is mapped
+#[cfg(not(windows))]
+fn open(path: &Path) -> Result<Reader, AssetError> {
+    // SAFETY: the compiler writes packs next to them and renames them over, a mapped pack never changes
+    unsafe { Reader::map(path) }
+}
+/// Windows can not rename over a mapped file, the pack is read so the compiler can replace it while the game runs
+#[cfg(windows)]
+fn open(path: &Path) -> Result<Reader, AssetError> {
+    Reader::new(path)
+}
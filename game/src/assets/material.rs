@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use pack::{MaterialRecord, TextureRecord, TextureFormat, ColorSpace, AlphaMode};
+
+use super::{Texture, VertexType};
+
+/// glTF material, `Assets::get_material` turns it into the material of the shader it was exported for
+#[allow(dead_code)]
+pub struct PbrMaterial {
+    pub name: String,
+    pub shader: VertexType,
+    pub base_color_factor: [f32;4],
+    pub base_color_texture: Option<String>,
+    pub normal_texture: Option<String>,
+    pub metallic_roughness_texture: Option<String>,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+    /// 1x1 texture of the base color factor, used when the material has no base color texture.
+    /// Named `<material>#factor`, record names can not contain `#` so no texture of a pack has its name
+    pub factor_texture: Arc<Texture>
+}
+impl PbrMaterial {
+    pub fn from_record(device: &wgpu::Device, queue: &wgpu::Queue, record: MaterialRecord) -> Self {
+        let factor = record.base_color_factor;
+        let factor_texture = Texture::from_record(device, queue, TextureRecord {
+            name: format!("{}#factor", record.name),
+            format: TextureFormat::Raw,
+            channels: 4,
+            color_space: ColorSpace::Srgb,
+            width: 1,
+            height: 1,
+            mips: vec![{
+                let [r, g, b] = [factor[0], factor[1], factor[2]].map(linear_to_srgb);
+                vec![r, g, b, (factor[3].clamp(0., 1.) * 255.).round() as u8]
+            }]
+        });
+        Self {
+            name: record.name,
+            shader: record.shader,
+            base_color_factor: factor,
+            base_color_texture: record.base_color_texture,
+            normal_texture: record.normal_texture,
+            metallic_roughness_texture: record.metallic_roughness_texture,
+            alpha_mode: record.alpha_mode,
+            double_sided: record.double_sided,
+            factor_texture: Arc::new(factor_texture)
+        }
+    }
+}
+
+/// glTF factors are linear, textures are uploaded as sRGB
+fn linear_to_srgb(v: f32) -> u8 {
+    let v = v.clamp(0., 1.);
+    let v = if v <= 0.0031308 { v * 12.92 } else { 1.055 * v.powf(1. / 2.4) - 0.055 };
+    (v * 255.).round() as u8
+}
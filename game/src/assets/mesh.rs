@@ -1,84 +1,173 @@
+use std::ops::Range;
+
+use pack::{Bounds, MeshRecord, Vertices, Indices, SubMeshRecord, JointRecord, VertexBasic, VertexU, VertexNU, VertexNUS, VertexNUT, VertexNUST, NO_JOINT};
 use wgpu::util::DeviceExt;
 
-use super::{Reader, VertexType, Joint, VertexNUS, VertexU, VertexNU};
+use super::{AssetError, VertexType, Joint, MAX_JOINTS};
+
+/// Part of a mesh drawn with its own material
+#[allow(dead_code)]
+pub struct SubMesh {
+    /// name of the material the mesh was exported with, empty for the default material
+    pub material: String,
+    pub indices: Range<u32>,
+    pub bounds: Bounds
+}
+
+/// Simplified version of the mesh, drawn from `distance` to the camera on
+pub struct Lod {
+    pub distance: f32,
+    /// indices of every sub-mesh, in the order of `Mesh::sub_meshes`
+    pub indices: Vec<Range<u32>>
+}
 
+#[allow(dead_code)]
 pub struct Mesh {
     pub name: String,
     pub vertex_type: VertexType,
     pub vertices_buffer: wgpu::Buffer,
     pub vertices_len: u32,
+    pub indices_buffer: wgpu::Buffer,
+    pub indices_len: u32,
+    pub index_format: wgpu::IndexFormat,
+    pub sub_meshes: Vec<SubMesh>,
+    /// at rest, `Armature::bounds` follows the animation
+    pub bounds: Bounds,
+    /// from the closest to the farthest
+    pub lods: Vec<Lod>,
     pub joints: Vec<Joint>
 }
 impl Mesh {
-    pub fn load(device: &wgpu::Device, reader: &mut Reader) -> Self {
-        let name = reader.read_string();
-        let vertex_type = VertexType::read(reader);
-    
-        let mut vertices_len = 0;
-        let vertices_buffer = match vertex_type {
-            VertexType::U => {
-                get_vertices_buffer::<VertexU>(device, reader, &mut vertices_len, |reader| {
-                    VertexU {
-                        position: reader.read_vec3(),
-                        uv: reader.read_vec2()
-                    }
-                })
-            }
-            VertexType::NU => {
-                get_vertices_buffer::<VertexNU>(device, reader, &mut vertices_len, |reader| {
-                    VertexNU {
-                        position: reader.read_vec3(),
-                        normal: reader.read_vec3(),
-                        uv: reader.read_vec2()
-                    }
-                })
-            }
-            VertexType::NUS => {
-                get_vertices_buffer::<VertexNUS>(device, reader, &mut vertices_len, |reader| {
-                    VertexNUS {
-                        position: reader.read_vec3(),
-                        normal: reader.read_vec3(),
-                        uv: reader.read_vec2(),
-                        joints: reader.read_joints(),
-                        weights: reader.read_vec4()
-                    }
-                })
-            }
-        };
-
-        let joints = match vertex_type {
-            VertexType::NUS => Joint::read(reader),
-            _ => Vec::new()
+    pub fn from_record(device: &wgpu::Device, record: MeshRecord) -> Result<Self, AssetError> {
+        if record.joints.len() > MAX_JOINTS {
+            return Err(AssetError::TooManyJoints { joints: record.joints.len(), maximum: MAX_JOINTS })
+        }
+        let joints = if record.vertices.vertex_type().has_joints() {
+            record.joints.iter().enumerate().map(|(id, joint)| Joint::from_record(id, joint)).collect()
+        } else {
+            Vec::new()
         };
-        
-        reader.read_end(&name);
-
-        Self {
-            name,
-            vertex_type,
+        let vertices_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(record.name.as_str()),
+            contents: record.vertices.bytes(),
+            usage: wgpu::BufferUsages::VERTEX
+        });
+        let indices_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some(record.name.as_str()),
+            contents: record.indices.bytes(),
+            usage: wgpu::BufferUsages::INDEX
+        });
+        Ok(Self {
+            vertex_type: record.vertices.vertex_type(),
+            vertices_len: record.vertices.len() as u32,
+            indices_len: record.indices.len() as u32,
+            index_format: match record.indices {
+                Indices::U16(_) => wgpu::IndexFormat::Uint16,
+                Indices::U32(_) => wgpu::IndexFormat::Uint32
+            },
+            name: record.name,
+            sub_meshes: record.sub_meshes.into_iter().map(|sub_mesh| SubMesh {
+                material: sub_mesh.material,
+                indices: sub_mesh.indices_start..sub_mesh.indices_start + sub_mesh.indices_len,
+                bounds: sub_mesh.bounds
+            }).collect(),
+            bounds: record.bounds,
+            lods: record.lods.into_iter().map(|lod| Lod { distance: lod.distance, indices: lod.indices }).collect(),
             vertices_buffer,
-            vertices_len,
+            indices_buffer,
             joints
+        })
+    }
+    /// Level to draw at `distance` from the camera, 0 is the full mesh
+    pub fn lod(&self, distance: f32) -> usize {
+        self.lods.iter().take_while(|lod| distance >= lod.distance).count()
+    }
+    /// Indices of a sub-mesh in a level, levels past the last one draw the last one
+    pub fn indices(&self, lod: usize, sub_mesh: usize) -> Range<u32> {
+        match lod.min(self.lods.len()) {
+            0 => self.sub_meshes[sub_mesh].indices.clone(),
+            lod => self.lods[lod - 1].indices[sub_mesh].clone()
         }
     }
-}
-
-#[inline]
-fn get_vertices_buffer<V: Copy + Clone + bytemuck::Pod + bytemuck::Zeroable>(
-    device: &wgpu::Device,
-    reader: &mut Reader,
-    vertices_len: &mut u32,
-    f: fn(&mut Reader) -> V
-) -> wgpu::Buffer {
-    let mut vertices = Vec::<V>::new();
-    let total_vertices = reader.read_u32() as usize;
-    for _ in 0..total_vertices {
-        vertices.push(f(reader));
+    /// Unit cube centered on the origin, drawn in place of a missing mesh.
+    /// Skinned cubes have a single joint at the origin so they can still get an armature
+    pub fn cube(device: &wgpu::Device, name: impl AsRef<str>, vertex_type: VertexType) -> Self {
+        let mut vertices = Vec::with_capacity(24);
+        let mut indices = Vec::with_capacity(36);
+        for axis in 0..3 {
+            for side in [-1f32, 1.] {
+                let mut normal = [0.;3];
+                normal[axis] = side;
+                let start = vertices.len() as u32;
+                for (u, v) in [(0., 0.), (1., 0.), (1., 1.), (0., 1.)] {
+                    let mut position = [0.;3];
+                    position[axis] = side * 0.5;
+                    position[(axis + 1) % 3] = (u - 0.5) * side;
+                    position[(axis + 2) % 3] = v - 0.5;
+                    vertices.push(VertexNU { position, normal, uv: [u, v] });
+                }
+                indices.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 3]);
+            }
+        }
+        let identity = [[1., 0., 0., 0.], [0., 1., 0., 0.], [0., 0., 1., 0.], [0., 0., 0., 1.]];
+        // u runs along the next axis after the one the face looks at
+        let tangent = |normal: [f32;3]| {
+            let axis = (0..3).find(|axis| normal[*axis] != 0.).unwrap();
+            let mut tangent = [0., 0., 0., 1.];
+            tangent[(axis + 1) % 3] = normal[axis];
+            tangent
+        };
+        let bounds = Bounds { min: [-0.5;3], max: [0.5;3], center: [0.;3], radius: 0.75f32.sqrt() };
+        let mut record = MeshRecord {
+            name: name.as_ref().to_string(),
+            indices: Indices::new(indices, vertices.len()),
+            sub_meshes: vec![SubMeshRecord { material: String::new(), indices_start: 0, indices_len: 36, bounds }],
+            bounds,
+            lods: Vec::new(),
+            joints: if vertex_type.has_joints() {
+                vec![JointRecord {
+                    name: "root".to_string(),
+                    parent: NO_JOINT,
+                    parents: Vec::new(),
+                    tpose: identity,
+                    tpose_local: identity,
+                    ibm: identity,
+                    bounds
+                }]
+            } else {
+                Vec::new()
+            },
+            vertices: match vertex_type.unquantized() {
+                VertexType::Basic => Vertices::Basic(vertices.iter().map(|v| VertexBasic { position: v.position }).collect()),
+                VertexType::U => Vertices::U(vertices.iter().map(|v| VertexU { position: v.position, uv: v.uv }).collect()),
+                VertexType::NU => Vertices::NU(vertices.into()),
+                VertexType::NUS => Vertices::NUS(vertices.iter().map(|v| VertexNUS {
+                    position: v.position,
+                    normal: v.normal,
+                    uv: v.uv,
+                    joints: [0;4],
+                    weights: [1., 0., 0., 0.]
+                }).collect()),
+                VertexType::NUT => Vertices::NUT(vertices.iter().map(|v| VertexNUT {
+                    position: v.position,
+                    normal: v.normal,
+                    uv: v.uv,
+                    tangent: tangent(v.normal)
+                }).collect()),
+                VertexType::NUST => Vertices::NUST(vertices.iter().map(|v| VertexNUST {
+                    position: v.position,
+                    normal: v.normal,
+                    uv: v.uv,
+                    tangent: tangent(v.normal),
+                    joints: [0;4],
+                    weights: [1., 0., 0., 0.]
+                }).collect()),
+                VertexType::NUQ | VertexType::NUSQ => unreachable!()
+            }
+        };
+        if vertex_type.is_quantized() {
+            record.vertices = record.vertices.quantize().unwrap()
+        }
+        Self::from_record(device, record).unwrap()
     }
-    *vertices_len = total_vertices as u32;
-    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-        label: None,
-        contents: bytemuck::cast_slice(&vertices),
-        usage: wgpu::BufferUsages::VERTEX
-    })
-}
\ No newline at end of file
+}
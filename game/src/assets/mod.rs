@@ -3,8 +3,15 @@ mod instances;  pub use instances::*;
 mod mesh;       pub use mesh::*;
 mod armature;   pub use armature::*;
 mod texture;    pub use texture::*;
-mod reader;     pub use reader::*;
+mod material;   pub use material::*;
 mod object;     pub use object::*;
 mod joint;      pub use joint::*;
+mod scene;      pub use scene::*;
+#[allow(clippy::module_inception)]
 mod assets;     pub use assets::*;
-pub mod vertex;     pub use vertex::*;
\ No newline at end of file
+mod hot_reload; pub use hot_reload::*;
+mod loader;     pub use loader::*;
+mod store;      pub use store::*;
+pub mod vertex;     pub use vertex::*;
+
+pub use pack::{Reader, AssetError, TocEntry, Record};
\ No newline at end of file
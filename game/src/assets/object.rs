@@ -2,11 +2,23 @@ use std::sync::{Arc, Mutex};
 
 use crate::{shaders::Material, context::Context, camera::Camera};
 
-use super::{Mesh, Instances, Armature, Animation};
+use super::{Mesh, Texture, Instances, Armature, Animation, LoadedAssets};
 
-pub struct Object {
+/// Mesh and materials an object is drawn with, replaced as a whole when one of them is hot reloaded
+pub struct ObjectAssets {
     pub mesh: Arc<Mesh>,
-    pub material: Material,
+    /// bound to the mesh's sub-meshes in order, the last one also covers every remaining sub-mesh
+    pub materials: Vec<Material>
+}
+impl ObjectAssets {
+    #[inline]
+    pub fn material(&self, sub_mesh: usize) -> &Material {
+        &self.materials[sub_mesh.min(self.materials.len() - 1)]
+    }
+}
+
+pub struct Object {
+    assets: Mutex<Arc<ObjectAssets>>,
     pub instances: Instances,
     pub armature: Option<Armature>
 }
@@ -14,12 +26,49 @@ impl Object {
     pub fn set_animation(&self, animation: Arc<Animation>) {
         self.armature.as_ref().expect("Object has no armature").set_animation(animation)
     }
+    /// Mesh and materials to draw the current frame with
+    #[inline]
+    pub fn assets(&self) -> Arc<ObjectAssets> {
+        self.assets.lock().unwrap().clone()
+    }
     pub fn update(&self, queue: &wgpu::Queue) {
-        self.instances.update(&queue);
+        self.instances.update(queue);
         if let Some(armature) = self.armature.as_ref() {
             armature.update(queue)
         }
     }
+    /// Switches to the new version of every reloaded asset the object uses
+    pub fn reload(&self, c: &Context, loaded: &LoadedAssets) {
+        let current = self.assets();
+        let mut mesh = current.mesh.clone();
+        if let Some(new) = c.assets.find_mesh(&mesh.name).filter(|_| loaded.meshes.contains(&mesh.name)) {
+            let readable = current.materials.iter().all(|material| material.vertex_types().contains(&new.vertex_type));
+            if !readable || new.joints.is_empty() != mesh.joints.is_empty() {
+                warn!("Mesh \"{}\" changed its vertex type or armature, restart the game to see it", mesh.name)
+            } else {
+                mesh = new
+            }
+        }
+        let reloaded = |texture: &Arc<Texture>| c.assets.find_texture(&texture.name).filter(|_| loaded.textures.contains(&texture.name));
+        let materials: Vec<Material> = current.materials.iter().map(|material| {
+            match (reloaded(material.texture()), material.normal().and_then(reloaded)) {
+                (None, None) => material.clone(),
+                (texture, normal) => material.with_textures(
+                    c,
+                    texture.unwrap_or_else(|| material.texture().clone()),
+                    normal.or_else(|| material.normal().cloned())
+                )
+            }
+        }).collect();
+
+        if let Some(armature) = self.armature.as_ref() {
+            if !Arc::ptr_eq(&mesh, &current.mesh) {
+                armature.set_mesh(mesh.clone())
+            }
+            armature.reload(&c.assets, loaded)
+        }
+        *self.assets.lock().unwrap() = Arc::new(ObjectAssets { mesh, materials });
+    }
 }
 
 pub struct Objects(pub Mutex<Vec<Arc<Object>>>);
@@ -33,49 +82,62 @@ impl Objects {
         &self,
         device: &wgpu::Device,
         mesh: Arc<Mesh>,
-        material: Material,
+        materials: Vec<Material>,
         maximum_instances: usize
     ) -> Arc<Object> {
+        assert!(!materials.is_empty(), "Object {} needs at least one material", mesh.name);
         let joints_len = mesh.joints.len();
         let object = Arc::new(Object {
-            mesh: mesh.clone(),
-            material,
-            instances: Instances::new(device, maximum_instances),
             armature: if joints_len > 0 {
-                Some(Armature::new(device, mesh))
+                Some(Armature::new(device, mesh.clone()))
             }else {
                 None
-            }
+            },
+            assets: Mutex::new(Arc::new(ObjectAssets { mesh, materials })),
+            instances: Instances::new(device, maximum_instances)
         });
         self.0.lock().unwrap().push(object.clone());
         object
     }
+    /// Every object with the assets it is drawn with this frame, a hot reload during the frame shows up on the next one
+    pub fn frame(&self) -> Vec<(Arc<Object>, Arc<ObjectAssets>)> {
+        self.0.lock().unwrap().iter().map(|object| (object.clone(), object.assets())).collect()
+    }
     pub fn draw<'r, 's: 'r>(
         render_pass: &mut wgpu::RenderPass<'r>,
         c: &'s Context,
-        objects: &'s Vec<Arc<Object>>,
+        objects: &'s [(Arc<Object>, Arc<ObjectAssets>)],
         camera: &'s Camera
     ) {
-        for object in objects.iter() {
+        let [x, y, z, _] = camera.values.get_position();
+        for (object, assets) in objects.iter() {
             object.update(&c.queue);
+            let lod = assets.mesh.lod(object.instances.distance([x, y, z]));
             render_pass.set_bind_group(0, &camera.bind_group, &[]);
-            render_pass.set_vertex_buffer(0, object.mesh.vertices_buffer.slice(..));
+            render_pass.set_vertex_buffer(0, assets.mesh.vertices_buffer.slice(..));
             render_pass.set_vertex_buffer(1, object.instances.buffer.slice(..));
-            match &object.material {
-                Material::BasicAnim(material) => {
-                    render_pass.set_pipeline(&c.shaders.basic_anim.render_pipeline);
-                    render_pass.set_bind_group(1, &material.bind_group, &[]);
-                    render_pass.set_bind_group(2, &object.armature.as_ref().unwrap().bind_group, &[]);
-                    render_pass.set_bind_group(3, &c.lights.sun.bind_group, &[]);
-                    render_pass.draw(0..object.mesh.vertices_len, 0..object.instances.get_buffer_len());
-                },
-                Material::Terrain(material) => {
-                    render_pass.set_pipeline(&c.shaders.terrain.render_pipeline);
-                    render_pass.set_bind_group(1, &material.texture.bind_group, &[]);
-                    render_pass.set_bind_group(2, &c.lights.sun.bind_group, &[]);
-                    render_pass.draw(0..object.mesh.vertices_len, 0..1);
+            render_pass.set_index_buffer(assets.mesh.indices_buffer.slice(..), assets.mesh.index_format);
+            for id in 0..assets.mesh.sub_meshes.len() {
+                let indices = assets.mesh.indices(lod, id);
+                // `Context::add_object` replaced meshes the shader can not read by cubes
+                match assets.material(id) {
+                    Material::BasicAnim(material) => {
+                        let pipeline = match c.shaders.basic_anim.pipeline(assets.mesh.vertex_type) { Some(v) => v, None => continue };
+                        render_pass.set_pipeline(pipeline);
+                        render_pass.set_bind_group(1, &material.bind_group, &[]);
+                        render_pass.set_bind_group(2, &object.armature.as_ref().unwrap().bind_group, &[]);
+                        render_pass.set_bind_group(3, &c.lights.sun.bind_group, &[]);
+                        render_pass.draw_indexed(indices, 0, 0..object.instances.get_buffer_len());
+                    },
+                    Material::Terrain(material) => {
+                        let pipeline = match c.shaders.terrain.pipeline(assets.mesh.vertex_type) { Some(v) => v, None => continue };
+                        render_pass.set_pipeline(pipeline);
+                        render_pass.set_bind_group(1, &material.bind_group, &[]);
+                        render_pass.set_bind_group(2, &c.lights.sun.bind_group, &[]);
+                        render_pass.draw_indexed(indices, 0, 0..object.instances.get_buffer_len());
+                    }
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
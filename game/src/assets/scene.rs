@@ -0,0 +1,58 @@
+use cgmath::Matrix4;
+use pack::SceneRecord;
+
+use super::InstanceTransform;
+
+#[allow(dead_code)]
+pub struct SceneNode {
+    pub name: String,
+    /// relative to the parent
+    pub transform: InstanceTransform,
+    pub mesh: Option<String>,
+    /// JSON of the glTF extras, empty when the node has none
+    pub extras: String,
+    /// ids in `Scene::nodes`
+    pub children: Vec<usize>
+}
+
+/// Node hierarchy exported from a glTF scene, `Context::spawn_scene` draws its meshes
+pub struct Scene {
+    pub name: String,
+    /// every parent comes before its children
+    pub nodes: Vec<SceneNode>,
+    pub roots: Vec<usize>
+}
+impl Scene {
+    pub fn from_record(record: SceneRecord) -> Self {
+        Self {
+            nodes: record.nodes.into_iter().map(|node| SceneNode {
+                name: node.name,
+                transform: InstanceTransform { position: node.translation, scale: node.scale, rotation: node.rotation },
+                mesh: node.mesh,
+                extras: node.extras,
+                children: node.children.into_iter().map(|child| child as usize).collect()
+            }).collect(),
+            roots: record.roots.into_iter().map(|root| root as usize).collect(),
+            name: record.name
+        }
+    }
+    /// Transform of every node in the space `root` places the scene in
+    pub fn transforms(&self, root: Matrix4<f32>) -> Vec<Matrix4<f32>> {
+        let mut res = vec![root; self.nodes.len()];
+        for id in self.roots.iter() {
+            res[*id] = root * self.nodes[*id].transform.mat();
+        }
+        // parents come first, so their transform is final when their children get theirs
+        for (id, node) in self.nodes.iter().enumerate() {
+            for child in node.children.iter() {
+                res[*child] = res[id] * self.nodes[*child].transform.mat();
+            }
+        }
+        res
+    }
+    /// Id of the first node named `name`
+    #[allow(dead_code)]
+    pub fn find(&self, name: impl AsRef<str>) -> Option<usize> {
+        self.nodes.iter().position(|node| node.name == name.as_ref())
+    }
+}
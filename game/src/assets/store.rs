@@ -0,0 +1,88 @@
+use std::{collections::HashMap, sync::Arc, marker::PhantomData};
+
+use super::{Mesh, Texture, Animation};
+
+/// Stable reference to a named asset, it keeps resolving when the asset is reloaded, unloaded or not loaded yet
+pub struct Handle<T>(usize, PhantomData<fn() -> T>);
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl<T> Copy for Handle<T> {}
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+impl<T> Eq for Handle<T> {}
+impl<T> std::hash::Hash for Handle<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+impl<T> std::fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Handle({})", self.0)
+    }
+}
+
+pub type MeshHandle = Handle<Mesh>;
+#[allow(dead_code)]
+pub type TextureHandle = Handle<Texture>;
+pub type AnimationHandle = Handle<Animation>;
+
+struct Slot<T> {
+    name: String,
+    asset: Option<Arc<T>>,
+    /// drawn in place of the asset while it is missing, built the first time it is needed
+    fallback: Option<Arc<T>>
+}
+
+/// Assets of one kind, found by name through a hash map.
+/// Every name ever asked for keeps its slot, so handles never dangle
+pub struct Store<T> {
+    ids: HashMap<String, usize>,
+    slots: Vec<Slot<T>>
+}
+impl<T> Store<T> {
+    pub fn new() -> Self {
+        Self {
+            ids: HashMap::new(),
+            slots: Vec::new()
+        }
+    }
+    /// Handle of the asset named `name`, loaded or not
+    pub fn handle(&mut self, name: &str) -> Handle<T> {
+        if let Some(id) = self.ids.get(name) {
+            return Handle(*id, PhantomData)
+        }
+        self.slots.push(Slot { name: name.to_string(), asset: None, fallback: None });
+        self.ids.insert(name.to_string(), self.slots.len() - 1);
+        Handle(self.slots.len() - 1, PhantomData)
+    }
+    pub fn get(&self, name: &str) -> Option<Arc<T>> {
+        self.ids.get(name).and_then(|id| self.slots[*id].asset.clone())
+    }
+    /// Adds the asset, or replaces the one with the same name
+    pub fn insert(&mut self, name: &str, asset: Arc<T>) {
+        let handle = self.handle(name);
+        let slot = &mut self.slots[handle.0];
+        slot.asset = Some(asset);
+        slot.fallback = None;
+    }
+    pub fn remove(&mut self, name: &str) -> Option<Arc<T>> {
+        self.ids.get(name).and_then(|id| self.slots[*id].asset.take())
+    }
+    /// The asset, or its fallback built by `fallback` when it is missing
+    pub fn resolve(&mut self, handle: Handle<T>, kind: &str, replacement: &str, fallback: impl FnOnce(&str) -> T) -> Arc<T> {
+        let slot = &mut self.slots[handle.0];
+        if let Some(asset) = slot.asset.as_ref() {
+            return asset.clone()
+        }
+        slot.fallback.get_or_insert_with(|| {
+            warn!("{kind} \"{}\" is not loaded, {replacement} is used in its place", slot.name);
+            Arc::new(fallback(&slot.name))
+        }).clone()
+    }
+}
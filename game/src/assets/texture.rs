@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use wgpu::{TextureUsages, Extent3d};
 
-use super::Reader;
+use pack::{TextureRecord, TextureFormat, ColorSpace};
 
 pub const DEFAULT_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
 pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
@@ -17,61 +17,77 @@ pub struct Texture {
 }
 
 impl Texture {
-    pub fn load(
+    /// Uploads every mip level, block compressed textures are decompressed when the device can not sample them.
+    /// sRGB textures are converted to linear when sampled, linear ones are sampled as stored
+    pub fn from_record(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
-        reader: &mut Reader
+        record: TextureRecord
     ) -> Self {
-        let name = reader.read_string();
-        let width = reader.read_u32();
-        let height = reader.read_u32();
+        let TextureRecord { name, format, channels, color_space, width, height, mut mips } = record;
+        let srgb = color_space == ColorSpace::Srgb;
+        let format = match format {
+            TextureFormat::Raw => match channels {
+                1 => wgpu::TextureFormat::R8Unorm,
+                2 => wgpu::TextureFormat::Rg8Unorm,
+                _ if srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+                _ => wgpu::TextureFormat::Rgba8Unorm
+            },
+            TextureFormat::Bc1 if device.features().contains(wgpu::Features::TEXTURE_COMPRESSION_BC) =>
+                if srgb { wgpu::TextureFormat::Bc1RgbaUnormSrgb } else { wgpu::TextureFormat::Bc1RgbaUnorm },
+            TextureFormat::Bc3 if device.features().contains(wgpu::Features::TEXTURE_COMPRESSION_BC) =>
+                if srgb { wgpu::TextureFormat::Bc3RgbaUnormSrgb } else { wgpu::TextureFormat::Bc3RgbaUnorm },
+            TextureFormat::Bc1 | TextureFormat::Bc3 => {
+                let decode = if format == TextureFormat::Bc1 { pack::decode_bc1 } else { pack::decode_bc3 };
+                for (level, mip) in mips.iter_mut().enumerate() {
+                    *mip = decode(mip, (width >> level).max(1), (height >> level).max(1));
+                }
+                if srgb { wgpu::TextureFormat::Rgba8UnormSrgb } else { wgpu::TextureFormat::Rgba8Unorm }
+            }
+        };
 
-        let len = (width * height) as usize;
-        let mut rgba = Vec::with_capacity(len * 4);
-        for _ in 0..len {
-            rgba.push(reader.read_u8());
-            rgba.push(reader.read_u8());
-            rgba.push(reader.read_u8());
-            rgba.push(255);
-        }
-        reader.read_end(&name);
-        
         let size = wgpu::Extent3d {
             width, height,
             depth_or_array_layers: 1
         };
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some(name.as_str()),
-            size: size,
-            mip_level_count: 1,
+            size,
+            mip_level_count: mips.len() as u32,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: DEFAULT_FORMAT,
+            format,
             usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST
         });
-        queue.write_texture(
-            wgpu::ImageCopyTexture {
-                texture: &texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-                aspect: wgpu::TextureAspect::All
-            },
-            &rgba,
-            wgpu::ImageDataLayout {
-                offset: 0,
-                bytes_per_row: std::num::NonZeroU32::new(4 * width),
-                rows_per_image: std::num::NonZeroU32::new(height)
-            },
-            size
-        );
+        let info = format.describe();
+        for (level, mip) in mips.iter().enumerate() {
+            let level_size = size.mip_level_size(level as u32, false).physical_size(format);
+            queue.write_texture(
+                wgpu::ImageCopyTexture {
+                    texture: &texture,
+                    mip_level: level as u32,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All
+                },
+                mip,
+                wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(
+                        level_size.width / info.block_dimensions.0 as u32 * info.block_size as u32
+                    ),
+                    rows_per_image: None
+                },
+                level_size
+            );
+        }
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
         let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
             address_mode_u: wgpu::AddressMode::ClampToEdge,
             address_mode_v: wgpu::AddressMode::ClampToEdge,
             address_mode_w: wgpu::AddressMode::ClampToEdge,
             mag_filter: wgpu::FilterMode::Linear,
-            min_filter: wgpu::FilterMode::Nearest,
-            mipmap_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
             ..Default::default()
         });
         let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
@@ -97,6 +113,37 @@ impl Texture {
             bind_group: Arc::new(bind_group)
         }
     }
+    /// Magenta and black checkerboard drawn in place of a missing texture
+    pub fn checkerboard(device: &wgpu::Device, queue: &wgpu::Queue, name: impl AsRef<str>) -> Self {
+        const SIZE: u32 = 64;
+        let mut pixels = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+        for y in 0..SIZE {
+            for x in 0..SIZE {
+                pixels.extend_from_slice(if (x / 8 + y / 8) % 2 == 0 { &[255, 0, 255, 255] } else { &[0, 0, 0, 255] });
+            }
+        }
+        Self::from_record(device, queue, TextureRecord {
+            name: name.as_ref().to_string(),
+            format: TextureFormat::Raw,
+            channels: 4,
+            color_space: ColorSpace::Srgb,
+            width: SIZE,
+            height: SIZE,
+            mips: vec![pixels]
+        })
+    }
+    /// 1x1 normal map pointing straight out of the surface
+    pub fn flat_normal(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        Self::from_record(device, queue, TextureRecord {
+            name: "flat_normal".to_string(),
+            format: TextureFormat::Raw,
+            channels: 4,
+            color_space: ColorSpace::Linear,
+            width: 1,
+            height: 1,
+            mips: vec![vec![128, 128, 255, 255]]
+        })
+    }
     pub fn blank(
         name: impl AsRef<str>,
         device: &wgpu::Device,
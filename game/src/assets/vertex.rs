@@ -1,63 +1,86 @@
-use super::Reader;
+pub use pack::{VertexBasic, VertexU, VertexNU, VertexNUS, VertexNUQ, VertexNUSQ, VertexNUT, VertexNUST, VertexType};
 
-#[repr(C)]
-#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct VertexNUS {
-    pub position: [f32;3],
-    pub normal: [f32;3],
-    pub uv: [f32;2],
-    pub joints: [u32;4],
-    pub weights: [f32;4]
+/// Vertex buffer layout of the vertex types decoded from the asset pack
+pub trait VertexLayout {
+    const LAYOUT: wgpu::VertexBufferLayout<'static>;
 }
-impl VertexNUS {
-    pub const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+
+impl VertexLayout for VertexNUS {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
         array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
         step_mode: wgpu::VertexStepMode::Vertex,
         attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2, 3 => Uint32x4, 4 => Float32x4]
     };
 }
 
-#[repr(C)]
-#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct VertexU {
-    pub position: [f32;3],
-    pub uv: [f32;2]
+/// The octahedral normal reaches the shader as a vec2, `shaders::vertex_wgsl` unfolds it
+impl VertexLayout for VertexNUSQ {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Snorm16x2, 2 => Unorm16x2, 3 => Uint8x4, 4 => Unorm8x4]
+    };
 }
-impl VertexU {
-    pub const _LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+
+/// Tangents go to location 7, 5 and 6 are taken by `InstanceTransform`
+impl VertexLayout for VertexNUST {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
         array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
         step_mode: wgpu::VertexStepMode::Vertex,
-        attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2]
+        attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2, 7 => Float32x4, 3 => Uint32x4, 4 => Float32x4]
     };
 }
 
-#[repr(C)]
-#[derive(Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct VertexNU {
-    pub position: [f32;3],
-    pub normal: [f32;3],
-    pub uv: [f32;2]
+impl VertexLayout for VertexU {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x2]
+    };
 }
-impl VertexNU {
-    pub const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+
+impl VertexLayout for VertexNU {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
         array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
         step_mode: wgpu::VertexStepMode::Vertex,
         attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2]
     };
 }
 
-pub enum VertexType {
-    U,
-    NU,
-    NUS
+impl VertexLayout for VertexNUQ {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Snorm16x2, 2 => Unorm16x2]
+    };
+}
+
+impl VertexLayout for VertexNUT {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2, 7 => Float32x4]
+    };
 }
-impl VertexType {
-    pub fn read(reader: &mut Reader) -> Self {
-        match reader.read_string().as_str() {
-            "U" => Self::U,
-            "NU" => Self::NU,
-            "NUS" => Self::NUS,
-            vt => panic!("Invalid vertex type: {}", vt)
-        }
+
+impl VertexLayout for VertexBasic {
+    const LAYOUT: wgpu::VertexBufferLayout<'static> = wgpu::VertexBufferLayout {
+        array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &wgpu::vertex_attr_array![0 => Float32x3]
+    };
+}
+
+/// Layout of the vertex buffers of meshes with `vertex_type`
+pub fn layout(vertex_type: VertexType) -> wgpu::VertexBufferLayout<'static> {
+    match vertex_type {
+        VertexType::Basic => VertexBasic::LAYOUT,
+        VertexType::U => VertexU::LAYOUT,
+        VertexType::NU => VertexNU::LAYOUT,
+        VertexType::NUS => VertexNUS::LAYOUT,
+        VertexType::NUQ => VertexNUQ::LAYOUT,
+        VertexType::NUSQ => VertexNUSQ::LAYOUT,
+        VertexType::NUT => VertexNUT::LAYOUT,
+        VertexType::NUST => VertexNUST::LAYOUT
     }
-}
\ No newline at end of file
+}
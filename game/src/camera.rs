@@ -44,7 +44,7 @@ impl CameraValues {
         Self {
             perspective,
             position: Vector3::new(0., 0., 0.),
-            rotation_x: SmoothValueBounded::new(-0.78539816, 0.0015, 0.12, -1.3, 0.),
+            rotation_x: SmoothValueBounded::new(-std::f32::consts::FRAC_PI_4, 0.0015, 0.12, -1.3, 0.),
             rotation_y: SmoothValue::new(0., 0.0015, 0.12),
             distance: SmoothValueBounded::new(4., 0.5, 0.1, 1., 10.),
             target: CameraTarget::Point(target),
@@ -72,7 +72,7 @@ impl CameraValues {
             vec3_to_point3(self.center),
             [0., 1., 0.].into()
         );
-        (self.perspective * view).into()
+        self.perspective * view
     }
     pub fn resize(&mut self, settings: &Settings, new_size: PhysicalSize<u32>) {
         let aspect = new_size.width as f32 / new_size.height as f32;
@@ -1,8 +1,8 @@
-use std::{sync::{Mutex, Arc}, path::Path};
+use std::{sync::{Mutex, Arc}, path::{Path, PathBuf}};
 use wgpu::{Surface, Device, Queue, SurfaceConfiguration};
 use winit::{event_loop::EventLoop, window::Window, dpi::PhysicalSize};
-use crate::{settings::Settings, utils, camera::Camera, window, shaders::{Shaders, Material},
-    assets::{Object, Mesh, Texture, Objects, Assets}, cursor::Cursor,
+use crate::{settings::Settings, utils, camera::Camera, window, shaders::{self, Shaders, Material},
+    assets::{Object, Mesh, Texture, Objects, Assets, InstanceTransform, AssetError, HotReload, Reload, LoadedAssets, PackLoad, LoadProgress}, cursor::Cursor,
     ui::{UI, Square, UIElementTexture}, light::Lights};
 
 pub struct Context {
@@ -20,9 +20,14 @@ pub struct Context {
     pub ui: UI,
     pub lights: Lights,
     pub assets: Assets,
-    pub character: Mutex<Option<Arc<Object>>>
+    pub hot_reload: HotReload,
+    pub character: Mutex<Option<Arc<Object>>>,
+    /// what to do once a background load is over, by pack
+    on_loaded: Mutex<Vec<(PathBuf, OnLoaded)>>
 }
 
+type OnLoaded = Box<dyn FnOnce(&Context)>;
+
 impl Context {
     pub fn new(event_loop: &EventLoop<()>) -> Self {
         crate::logger::start();
@@ -30,7 +35,7 @@ impl Context {
 
         let instance = wgpu::Instance::new(wgpu::Backends::all());
 
-        let window = window::new(&settings, &event_loop);
+        let window = window::new(&settings, event_loop);
         
         let surface = unsafe { instance.create_surface(&window) };
         let adapter = utils::create_adapter(&instance, &surface);
@@ -42,10 +47,10 @@ impl Context {
         let camera = Camera::new(&settings, &device, &window, &cursor);
         let depth_texture = Texture::depth("Main depth texture", &device, surface_config.width, surface_config.height);
     
-        let shaders = Shaders::new(&device, surface_config.format);
+        let shaders = Shaders::new(&device, &queue, surface_config.format);
 
         let ui = UI::new(&device, &surface_config);
-        let lights = Lights::new(&device);
+        let lights = Lights::new(&settings, &device);
 
         Self {
             window, settings, surface, device, queue, cursor, shaders, ui, lights,
@@ -54,7 +59,9 @@ impl Context {
             surface_config: Mutex::new(surface_config),
             objects: Objects::new(),
             character: Mutex::new(None),
-            assets: Assets::new()
+            assets: Assets::new(),
+            hot_reload: HotReload::new(),
+            on_loaded: Mutex::new(Vec::new())
         }
     }
     pub fn resize(&self, new_size: PhysicalSize<u32>) {
@@ -66,13 +73,64 @@ impl Context {
         self.camera.lock().unwrap().resize(&self.settings, new_size);
         *self.depth_texture.lock().unwrap() = Texture::depth("Main depth texture", &self.device, surface_config.width, surface_config.height);
     }
+    /// Adds an object drawn with `materials`, a mesh their shader can not read is drawn as a cube
     pub fn add_object(
         &self,
-        mesh: Arc<Mesh>,
-        material: Material,
+        mut mesh: Arc<Mesh>,
+        materials: Vec<Material>,
         maximum_instances: usize
     ) -> Arc<Object> {
-        self.objects.add(&self.device, mesh, material, maximum_instances)
+        if let Some(material) = materials.iter().find(|material| !material.vertex_types().contains(&mesh.vertex_type)) {
+            let names: Vec<&str> = material.vertex_types().iter().map(|vertex_type| vertex_type.name()).collect();
+            error!("Mesh \"{}\" has {} vertices, but its material needs {}, a cube is drawn instead",
+                mesh.name, mesh.vertex_type.name(), names.join(", "));
+            mesh = Arc::new(Mesh::cube(&self.device, &mesh.name, material.vertex_types()[0]))
+        }
+        self.objects.add(&self.device, mesh, materials, maximum_instances)
+    }
+    /// Adds an object for every mesh of a loaded scene, each node drawing it is one of its instances placed by `root`
+    #[allow(dead_code)]
+    pub fn spawn_scene(&self, name: impl AsRef<str>, root: InstanceTransform) -> Vec<Arc<Object>> {
+        let name = name.as_ref();
+        let scene = match self.assets.find_scene(name) {
+            Some(v) => v,
+            None => {
+                warn!("Scene \"{name}\" not found, nothing is spawned");
+                return Vec::new()
+            }
+        };
+        let mut meshes: Vec<(&str, Vec<InstanceTransform>)> = Vec::new();
+        for (node, transform) in scene.nodes.iter().zip(scene.transforms(root.mat())) {
+            let mesh = match node.mesh.as_deref() { Some(v) => v, None => continue };
+            let transform = InstanceTransform::from_mat(transform);
+            match meshes.iter_mut().find(|(name, _)| *name == mesh) {
+                Some((_, transforms)) => transforms.push(transform),
+                None => meshes.push((mesh, vec![transform]))
+            }
+        }
+        meshes.into_iter().map(|(mesh, transforms)| {
+            let mesh = self.assets.get_mesh(self, mesh);
+            let materials = self.mesh_materials(&mesh);
+            // joints place skinned meshes, glTF ignores the transform of their nodes
+            let transforms = if mesh.joints.is_empty() { transforms } else { vec![root; transforms.len()] };
+            let object = self.add_object(mesh, materials, transforms.len());
+            for transform in transforms {
+                object.instances.add(transform);
+            }
+            object
+        }).collect()
+    }
+    /// The materials the sub-meshes of `mesh` were exported with, built by `Assets::get_material`.
+    /// Sub-meshes whose material is not loaded are drawn with a checkerboard
+    pub fn mesh_materials(&self, mesh: &Mesh) -> Vec<Material> {
+        mesh.sub_meshes.iter().map(|sub_mesh| {
+            self.assets.get_material(self, &sub_mesh.material).unwrap_or_else(|| {
+                let texture = Arc::new(Texture::checkerboard(&self.device, &self.queue, &sub_mesh.material));
+                // `add_object` draws a cube for vertices no shader reads
+                Material::for_vertex_type(self, mesh.vertex_type, texture.clone(), None)
+                    .unwrap_or_else(|| shaders::basic_anim::Material::new(self, texture, None))
+            })
+        }).collect()
     }
     pub fn add_square(
         &self,
@@ -87,7 +145,97 @@ impl Context {
         self.ui.squares.lock().unwrap().push(square.clone());
         square
     }
-    pub fn load_assets(&self, path: impl AsRef<Path>) {
-        self.assets.load(self, path)
+    /// Loads a pack, more can be loaded next to it and unloaded with `unload_pack`.
+    /// Debug builds reload it while the game runs when it changes
+    #[allow(dead_code)]
+    pub fn load_pack(&self, path: impl AsRef<Path>) -> Result<(), AssetError> {
+        self.assets.load(self, &path)?;
+        if cfg!(debug_assertions) {
+            self.hot_reload.watch(path)
+        }
+        Ok(())
+    }
+    /// Starts loading a pack on worker threads, a loading screen can follow it with `loading_progress`.
+    /// Its assets can only be used once it finished: `on_loaded` runs in the frame `upload_assets` found it done,
+    /// also when it failed, the assets it could not load are replaced by fallbacks
+    pub fn load_pack_background(&self, path: impl AsRef<Path>, on_loaded: impl FnOnce(&Context) + 'static) -> Arc<PackLoad> {
+        self.on_loaded.lock().unwrap().push((path.as_ref().to_path_buf(), Box::new(on_loaded)));
+        self.assets.load_background(path)
+    }
+    pub fn loading_progress(&self) -> LoadProgress {
+        self.assets.progress()
+    }
+    /// Puts what the background loads decoded on the GPU, called once per frame.
+    /// The window title shows how far the loads are
+    pub fn upload_assets(&self) {
+        let finished = self.assets.upload_loading(self);
+        let progress = self.loading_progress();
+        if progress.assets_total > 0 {
+            self.window.set_title(&format!("{} - loading {:.0}%", window::TITLE, progress.fraction() * 100.))
+        } else if !finished.is_empty() {
+            self.window.set_title(window::TITLE)
+        }
+        for (path, res) in finished {
+            match res {
+                Ok(loaded) => {
+                    if cfg!(debug_assertions) {
+                        self.hot_reload.watch(&path)
+                    }
+                    for object in self.objects.0.lock().unwrap().iter() {
+                        object.reload(self, &loaded)
+                    }
+                },
+                Err(e) => error!("Can not load assets: {e}")
+            }
+            let on_loaded = {
+                let mut on_loaded = self.on_loaded.lock().unwrap();
+                on_loaded.iter().position(|(v, _)| *v == path).map(|i| on_loaded.remove(i).1)
+            };
+            if let Some(on_loaded) = on_loaded {
+                on_loaded(self)
+            }
+        }
+    }
+    /// Releases the assets only this pack has, the GPU memory of each one is freed once no object holds it anymore
+    #[allow(dead_code)]
+    pub fn unload_pack(&self, path: impl AsRef<Path>) {
+        self.hot_reload.unwatch(&path);
+        if !self.assets.unload(&path) {
+            warn!("Pack {} is not loaded", path.as_ref().display())
+        }
+    }
+    /// Stops drawing an object, its assets are freed with it when their packs were unloaded
+    #[allow(dead_code)]
+    pub fn remove_object(&self, object: &Arc<Object>) {
+        self.objects.0.lock().unwrap().retain(|v| !Arc::ptr_eq(v, object))
+    }
+    /// Loads the assets changed since the last frame and switches the objects using them to the new versions
+    pub fn apply_hot_reload(&self) {
+        for reload in self.hot_reload.take() {
+            let res = match reload {
+                Reload::Pack(path) if self.assets.is_loaded(&path) => {
+                    info!("Reloading {}", path.display());
+                    self.assets.load(self, path)
+                },
+                Reload::Pack(_) => continue,
+                Reload::Assets(names) => {
+                    info!("Reloading {names:?}");
+                    self.hot_reload.packs().iter().try_fold(LoadedAssets::default(), |mut loaded, path| {
+                        loaded.extend(self.assets.load_assets(self, path, &names)?);
+                        Ok(loaded)
+                    })
+                }
+            };
+            let loaded = match res {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("Hot reload failed: {e}");
+                    continue
+                }
+            };
+            for object in self.objects.0.lock().unwrap().iter() {
+                object.reload(self, &loaded)
+            }
+        }
     }
 }
\ No newline at end of file
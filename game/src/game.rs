@@ -60,7 +60,8 @@ impl Game {
                 },
                 Event::MainEventsCleared => c.window.request_redraw(),
                 Event::RedrawRequested(_) => {
-                    
+                    c.apply_hot_reload();
+                    c.upload_assets();
                     c.lights.sun.update(&c.queue);
                     c.camera.lock().unwrap().update(&c.queue, &c.cursor);
 
@@ -75,7 +76,7 @@ impl Game {
         let mut encoder = c.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
         
         c.lights.update(&c.queue);
-        c.lights.draw(&c);
+        c.lights.draw(c);
 
         let output_texture = match c.surface.get_current_texture() {
             Ok(v) => v,
@@ -85,7 +86,7 @@ impl Game {
         let view = output_texture.texture.create_view(&wgpu::TextureViewDescriptor::default());
         let depth_texture = c.depth_texture.lock().unwrap();
 
-        let objects = &c.objects.0.lock().unwrap();
+        let objects = c.objects.frame();
         let camera = &c.camera.lock().unwrap();
         let squares = &c.ui.squares.lock().unwrap();
         
@@ -109,7 +110,7 @@ impl Game {
                     stencil_ops: None
                 })
             });
-            Objects::draw(&mut render_pass, c, objects, camera);
+            Objects::draw(&mut render_pass, c, &objects, camera);
             UI::draw(&mut render_pass, c, squares);
         }
 
@@ -1,13 +1,17 @@
-use crate::assets::{InstanceTransform, DEPTH_FORMAT, DEFAULT_FORMAT};
+use crate::assets::{InstanceTransform, VertexLayout, DEPTH_FORMAT, DEFAULT_FORMAT, VertexType};
 
 pub struct Shader {
-    pub render_pipeline: wgpu::RenderPipeline
+    /// one for every vertex type the shader reads
+    pub render_pipelines: Vec<(VertexType, wgpu::RenderPipeline)>
 }
 impl Shader {
     pub fn new(device: &wgpu::Device) -> Self {
         log::info!("Creating basic_anim directional light shader");
-        let shader = device.create_shader_module(wgpu::include_wgsl!("./shader.wgsl").into());
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("basic_anim directional light shader"),
+            source: wgpu::ShaderSource::Wgsl(format!("{}{}", crate::shaders::INSTANCE_WGSL, include_str!("./shader.wgsl")).into())
+        });
+        let create_pipeline = |vertex_type: VertexType| device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("basic_anim directional light shader render pipeline"),
             layout: Some(&device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("basic_anim directional light shader render pipeline layout"),
@@ -21,12 +25,8 @@ impl Shader {
                 module: &shader,
                 entry_point: "vs_main",
                 buffers: &[
-                    crate::assets::vertex::VertexNUS::LAYOUT,
-                    wgpu::VertexBufferLayout {
-                        array_stride: std::mem::size_of::<InstanceTransform>() as wgpu::BufferAddress,
-                        step_mode: wgpu::VertexStepMode::Instance,
-                        attributes: &wgpu::vertex_attr_array![5 => Float32x3, 6 => Float32x3]
-                    }
+                    crate::assets::vertex::layout(vertex_type),
+                    InstanceTransform::LAYOUT
                 ]
             },
             fragment: Some(wgpu::FragmentState {
@@ -62,7 +62,11 @@ impl Shader {
             multiview: None
         });
         Self {
-            render_pipeline
+            render_pipelines: crate::shaders::basic_anim::VERTEX_TYPES.iter().map(|vertex_type| (*vertex_type, create_pipeline(*vertex_type))).collect()
         }
     }
+    /// Pipeline reading the vertex buffers of `vertex_type`, None when the shader does not read them
+    pub fn pipeline(&self, vertex_type: VertexType) -> Option<&wgpu::RenderPipeline> {
+        crate::shaders::find_pipeline(&self.render_pipelines, vertex_type)
+    }
 }
\ No newline at end of file
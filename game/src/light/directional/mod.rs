@@ -27,6 +27,8 @@ pub struct DirectionalLight {
 
     pub direction: Mutex<[f32;3]>,
     pub size: Mutex<f32>,
+    /// levels the shadows are drawn coarser than the objects, a fine silhouette is lost in the shadow map anyway
+    pub lod_bias: usize,
     pub needs_update: AtomicBool
 }
 impl DirectionalLight {
@@ -34,7 +36,8 @@ impl DirectionalLight {
         device: &wgpu::Device,
         direction: [f32;3],
         size: f32,
-        texture_size: u32
+        texture_size: u32,
+        lod_bias: usize
     ) -> Self {
         let buffer = device.create_buffer_init(
             &wgpu::util::BufferInitDescriptor {
@@ -76,6 +79,7 @@ impl DirectionalLight {
             depth_texture: Texture::depth("Directional light depth texture", device, texture_size, texture_size),
             direction: Mutex::new(direction),
             size: Mutex::new(size),
+            lod_bias,
             needs_update: AtomicBool::new(false)
         }
     }
@@ -105,7 +109,8 @@ impl DirectionalLight {
     }
     pub fn draw(&self, c: &Context) {
         let mut encoder = c.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-        let objects = &c.objects.0.lock().unwrap();
+        let objects = c.objects.frame();
+        let [x, y, z, _] = c.camera.lock().unwrap().values.get_position();
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: None,
@@ -126,20 +131,27 @@ impl DirectionalLight {
                     stencil_ops: None
                 })
             });
-            for object in objects.iter() {
+            for (object, assets) in objects.iter() {
                 object.update(&c.queue);
                 render_pass.set_bind_group(0, &self.bind_group, &[]);
-                render_pass.set_vertex_buffer(0, object.mesh.vertices_buffer.slice(..));
+                render_pass.set_vertex_buffer(0, assets.mesh.vertices_buffer.slice(..));
                 render_pass.set_vertex_buffer(1, object.instances.buffer.slice(..));
-                match &object.material {
-                    Material::BasicAnim(_) => {
-                        render_pass.set_pipeline(&self.basic_anim.render_pipeline);
-                        render_pass.set_bind_group(1, &object.armature.as_ref().unwrap().bind_group, &[]);
-                        render_pass.draw(0..object.mesh.vertices_len, 0..object.instances.get_buffer_len());
-                    }
-                    Material::Terrain(_) => {
-                        render_pass.set_pipeline(&self.terrain.render_pipeline);
-                        render_pass.draw(0..object.mesh.vertices_len, 0..1);
+                render_pass.set_index_buffer(assets.mesh.indices_buffer.slice(..), assets.mesh.index_format);
+                let lod = assets.mesh.lod(object.instances.distance([x, y, z])) + self.lod_bias;
+                for id in 0..assets.mesh.sub_meshes.len() {
+                    let indices = assets.mesh.indices(lod, id);
+                    match assets.material(id) {
+                        Material::BasicAnim(_) => {
+                            let pipeline = match self.basic_anim.pipeline(assets.mesh.vertex_type) { Some(v) => v, None => continue };
+                            render_pass.set_pipeline(pipeline);
+                            render_pass.set_bind_group(1, &object.armature.as_ref().unwrap().bind_group, &[]);
+                            render_pass.draw_indexed(indices, 0, 0..object.instances.get_buffer_len());
+                        }
+                        Material::Terrain(_) => {
+                            let pipeline = match self.terrain.pipeline(assets.mesh.vertex_type) { Some(v) => v, None => continue };
+                            render_pass.set_pipeline(pipeline);
+                            render_pass.draw_indexed(indices, 0, 0..object.instances.get_buffer_len());
+                        }
                     }
                 }
             }
@@ -1,13 +1,17 @@
-use crate::assets::{DEFAULT_FORMAT, DEPTH_FORMAT};
+use crate::assets::{InstanceTransform, VertexLayout, DEFAULT_FORMAT, DEPTH_FORMAT, VertexType};
 
 pub struct Shader {
-    pub render_pipeline: wgpu::RenderPipeline
+    /// one for every vertex type the shader reads
+    pub render_pipelines: Vec<(VertexType, wgpu::RenderPipeline)>
 }
 
 impl Shader {
     pub fn new(device: &wgpu::Device) -> Self {
         log::info!("Creating terrain directional light shader");
-        let shader = device.create_shader_module(wgpu::include_wgsl!("./shader.wgsl").into());
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("terrain directional light shader"),
+            source: wgpu::ShaderSource::Wgsl(format!("{}{}", crate::shaders::INSTANCE_WGSL, include_str!("./shader.wgsl")).into())
+        });
         let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Terrain directional light shader render pipeline layout"),
             bind_group_layouts: &[
@@ -15,14 +19,15 @@ impl Shader {
             ],
             push_constant_ranges: &[]
         });
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let create_pipeline = |vertex_type: VertexType| device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Terrain directional light shader render pipeline"),
             layout: Some(&render_pipeline_layout),
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: "vs_main",
                 buffers: &[
-                    crate::assets::vertex::VertexNU::LAYOUT
+                    crate::assets::vertex::layout(vertex_type),
+                    InstanceTransform::LAYOUT
                 ]
             },
             fragment: Some(wgpu::FragmentState {
@@ -58,7 +63,11 @@ impl Shader {
             multiview: None
         });
         Self {
-            render_pipeline
+            render_pipelines: crate::shaders::terrain::VERTEX_TYPES.iter().map(|vertex_type| (*vertex_type, create_pipeline(*vertex_type))).collect()
         }
     }
+    /// Pipeline reading the vertex buffers of `vertex_type`, None when the shader does not read them
+    pub fn pipeline(&self, vertex_type: VertexType) -> Option<&wgpu::RenderPipeline> {
+        crate::shaders::find_pipeline(&self.render_pipelines, vertex_type)
+    }
 }
\ No newline at end of file
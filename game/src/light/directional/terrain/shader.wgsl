@@ -13,9 +13,9 @@ struct Output {
 };
 
 @vertex
-fn vs_main(vertex: Vertex) -> Output {
+fn vs_main(vertex: Vertex, transform: Transform) -> Output {
     var out: Output;
-    out.position = dir_light.projection * vec4<f32>(vertex.position, 1.0);
+    out.position = dir_light.projection * vec4<f32>(instance_position(transform, vertex.position), 1.0);
     return out;
 }
 
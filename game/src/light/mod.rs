@@ -1,4 +1,4 @@
-use crate::context::Context;
+use crate::{context::Context, settings::Settings};
 
 pub mod directional;
 
@@ -7,9 +7,9 @@ pub struct Lights {
 }
 
 impl Lights {
-    pub fn new(device: &wgpu::Device) -> Self {
+    pub fn new(settings: &Settings, device: &wgpu::Device) -> Self {
         Self {
-            sun: directional::DirectionalLight::new(device, [45.,45.,0.], 5.0, 1024*4)
+            sun: directional::DirectionalLight::new(device, [45.,45.,0.], 5.0, 1024*4, settings.shadow_lod_bias)
         }
     }
     pub fn update(&self, queue: &wgpu::Queue) {
@@ -28,8 +28,8 @@ pub fn start() {
             } else {
                 let now = Local::now();
                 let timestamp = format!("{:02}:{:02}:{:02}", now.hour(), now.minute(), now.second());
-                let module = match record.module_path() { Some(v)=>v, None=>"" };
-                let line = match record.line() { Some(v)=>v, None=>0 };
+                let module = record.module_path().unwrap_or_default();
+                let line = record.line().unwrap_or_default();
                 let styled_level = buf.default_styled_level(level);
                 append_log(format!("{timestamp} {styled_level} {module}:{line} {args}\r\n"));
                 writeln!(buf, "\x1b[90m{timestamp} {styled_level} \x1b[96m{module}:{line}\x1b[0m {args}")
@@ -42,5 +42,5 @@ pub fn start() {
 
 #[inline]
 pub fn append_log(v: String) {
-    FILE.lock().unwrap().write(v.as_bytes()).unwrap();
+    FILE.lock().unwrap().write_all(v.as_bytes()).unwrap();
 }
\ No newline at end of file
@@ -19,23 +19,22 @@ fn main() {
     let game = game::Game::new();
     {
         let c = &game.context;
-        c.load_assets("./assets/compiled.bin");
-        c.add_object(
-            c.assets.get_mesh("terrain_01"),
-            shaders::terrain::Material::new(c.assets.get_texture("terrain_01")), 0
-        );
-        let mutant = c.add_object(
-            c.assets.get_mesh("ch"),
-            shaders::basic_anim::Material::new(c.assets.get_texture("ch_diffuse")), 1
-        );
-        mutant.instances.add(assets::InstanceTransform { position: [0.;3], scale: [0.01,0.01,0.01] });
-        mutant.set_animation(c.assets.get_animation("ch_idle"));
-        *c.character.lock().unwrap() = Some(mutant.clone());
-        c.camera.lock().unwrap().set_target(camera::CameraTarget::Joint {
-            object: mutant.clone(),
-            offset: [0.,0.5,0.].into(),
-            scale: 0.01,
-            joint_id: 0
+        // the first frames are drawn while the pack is decoded, the objects are added once it is on the GPU
+        c.load_pack_background("./assets/compiled.bin", |c| {
+            let terrain = c.assets.get_mesh(c, "terrain_01");
+            let terrain = c.add_object(terrain.clone(), c.mesh_materials(&terrain), 1);
+            terrain.instances.add(assets::InstanceTransform::IDENTITY);
+            let mutant = c.assets.get_mesh(c, "ch");
+            let mutant = c.add_object(mutant.clone(), c.mesh_materials(&mutant), 1);
+            mutant.instances.add(assets::InstanceTransform { scale: [0.01,0.01,0.01], ..assets::InstanceTransform::IDENTITY });
+            mutant.set_animation(c.assets.get_animation("ch_idle"));
+            *c.character.lock().unwrap() = Some(mutant.clone());
+            c.camera.lock().unwrap().set_target(camera::CameraTarget::Joint {
+                object: mutant,
+                offset: [0.,0.5,0.].into(),
+                scale: 0.01,
+                joint_id: 0
+            });
         });
         c.add_square(0.3, -0.3, 0.25, 0.25, [[1.0,1.,1.,0.5];4], ui::UIElementTexture::SunDepthBuffer);
     }
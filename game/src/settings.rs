@@ -13,7 +13,9 @@ pub struct Settings {
     pub vsync: bool,
     pub fov: f32,
     pub near: f32,
-    pub far: f32
+    pub far: f32,
+    /// levels of detail the shadows are drawn coarser than the objects
+    pub shadow_lod_bias: usize
 }
 impl Settings {
     pub fn read() -> Self {
@@ -23,24 +25,24 @@ impl Settings {
         let set_path = set_dir.join("settings.json");
         info!("Settings path: {set_path:?}");
 
-        std::fs::create_dir_all(&set_dir).expect(&format!("Error creating directory: {set_dir:?}", ));
+        std::fs::create_dir_all(&set_dir).unwrap_or_else(|e| panic!("Error creating directory: {set_dir:?}, {e}"));
         
         let mut file = std::fs::OpenOptions::new().create(true).read(true).write(true).truncate(false).open(&set_path)
-            .expect(&format!("Failed to open path: {set_path:?}"));
+            .unwrap_or_else(|e| panic!("Failed to open path: {set_path:?}, {e}"));
         
         let mut content = String::new();
-        file.read_to_string(&mut content).expect(&format!("Error reading file: {set_path:?}"));
+        file.read_to_string(&mut content).unwrap_or_else(|e| panic!("Error reading file: {set_path:?}, {e}"));
 
-        if content.len() == 0 {
+        if content.is_empty() {
             let default = Self::get_default_json();
-            default.write_pretty(&mut file, 4).expect(&format!("Error writing to: {set_path:?}"));
-            return Self::from_json(default)
+            default.write_pretty(&mut file, 4).unwrap_or_else(|e| panic!("Error writing to: {set_path:?}, {e}"));
+            Self::from_json(default)
         }else {
             Self::from_json(json::parse(&content).unwrap())
         }
     }
     pub fn from_json(json: JsonValue) -> Self {
-        const ERR: &'static str = "Invalid settings";
+        const ERR: &str = "Invalid settings";
         info!("Settings loaded: {json}");
         let window = &json["window"];
         let size = &window["size"];
@@ -64,7 +66,9 @@ impl Settings {
             vsync: window["vsync"].as_bool().expect(ERR),
             fov: window["fov"].as_f32().expect(ERR),
             near: window["near"].as_f32().expect(ERR),
-            far: window["far"].as_f32().expect(ERR)
+            far: window["far"].as_f32().expect(ERR),
+            // settings files written before it existed keep the default
+            shadow_lod_bias: window["shadow_lod_bias"].as_usize().unwrap_or(1)
         }
     }
     pub fn get_default_json() -> JsonValue {
@@ -78,7 +82,8 @@ impl Settings {
                 vsync: true,
                 fov: 90.,
                 near: 0.01,
-                far: 1000.
+                far: 1000.,
+                shadow_lod_bias: 1
             }
         }
     }
@@ -3,30 +3,25 @@ mod material;
 pub use material::Material;
 use wgpu::ShaderModuleDescriptor;
 
-use crate::{assets::{InstanceTransform, DEPTH_FORMAT}, light::directional::directional_light_bind_group_layout};
+use crate::{assets::{InstanceTransform, VertexLayout, DEPTH_FORMAT, VertexType}, light::directional::directional_light_bind_group_layout};
+
+/// Skinned vertices, with or without tangents
+pub const VERTEX_TYPES: [VertexType; 3] = [VertexType::NUS, VertexType::NUSQ, VertexType::NUST];
 
 pub struct Shader {
-    pub render_pipeline: wgpu::RenderPipeline
+    /// one for every vertex type the shader reads
+    pub render_pipelines: Vec<(VertexType, wgpu::RenderPipeline)>
 }
 
 impl Shader {
     pub fn new(device: &wgpu::Device, surface_texture_format: wgpu::TextureFormat) -> Self {
         log::info!("Creating basic_anim shader");
         let a = 0.001;
-        let shader = device.create_shader_module(ShaderModuleDescriptor {
+        let shader = |vertex_type: VertexType| device.create_shader_module(ShaderModuleDescriptor {
             label: None,
             source: wgpu::ShaderSource::Wgsl(format!("
-struct Vertex {{
-    @location(0) position: vec3<f32>,
-    @location(1) normal: vec3<f32>,
-    @location(2) uv: vec2<f32>,
-    @location(3) joints: vec4<u32>,
-    @location(4) weights: vec4<f32>
-}};
-struct Transform {{
-    @location(5) position: vec3<f32>,
-    @location(6) scale: vec3<f32>
-}};
+{vertex}
+{instance}
 
 struct Camera {{
     @location(0) projection: mat4x4<f32>,
@@ -59,23 +54,24 @@ struct Output {{
     @location(0) position_sun_space: vec4<f32>,
     @location(1) vertex_position: vec3<f32>,
     @location(2) normal: vec3<f32>,
-    @location(3) uv: vec2<f32>
+    @location(3) uv: vec2<f32>,
+    @location(4) tangent: vec4<f32>
 }};
 
 fn apply_skin_pose(vertex: Vertex, v3: vec3<f32>) -> vec3<f32> {{
     let v4 = vec4<f32>(v3, 1.0);
     var res = (skin_poses.mats[vertex.joints[0]] * v4) * vertex.weights[0];
-    if (vertex.joints[1] != 255u) {{ res += (skin_poses.mats[vertex.joints[1]] * v4) * vertex.weights[1]; }}
-    if (vertex.joints[2] != 255u) {{ res += (skin_poses.mats[vertex.joints[2]] * v4) * vertex.weights[2]; }}
-    if (vertex.joints[3] != 255u) {{ res += (skin_poses.mats[vertex.joints[3]] * v4) * vertex.weights[3]; }}
+    res += (skin_poses.mats[vertex.joints[1]] * v4) * vertex.weights[1];
+    res += (skin_poses.mats[vertex.joints[2]] * v4) * vertex.weights[2];
+    res += (skin_poses.mats[vertex.joints[3]] * v4) * vertex.weights[3];
     return res.xyz;
 }}
 fn apply_skin_rotation(vertex: Vertex, v3: vec3<f32>) -> vec3<f32> {{
     let v4 = vec4<f32>(v3, 1.0);
     var res = (skin_rotations.mats[vertex.joints[0]] * v4) * vertex.weights[0];
-    if (vertex.joints[1] != 255u) {{ res += (skin_rotations.mats[vertex.joints[1]] * v4) * vertex.weights[1]; }}
-    if (vertex.joints[2] != 255u) {{ res += (skin_rotations.mats[vertex.joints[2]] * v4) * vertex.weights[2]; }}
-    if (vertex.joints[3] != 255u) {{ res += (skin_rotations.mats[vertex.joints[3]] * v4) * vertex.weights[3]; }}
+    res += (skin_rotations.mats[vertex.joints[1]] * v4) * vertex.weights[1];
+    res += (skin_rotations.mats[vertex.joints[2]] * v4) * vertex.weights[2];
+    res += (skin_rotations.mats[vertex.joints[3]] * v4) * vertex.weights[3];
     return res.xyz;
 }}
 
@@ -83,11 +79,13 @@ fn apply_skin_rotation(vertex: Vertex, v3: vec3<f32>) -> vec3<f32> {{
 fn vs_main(vertex: Vertex, transform: Transform) -> Output {{
     var out: Output;
     out.uv = vertex.uv;
-    out.vertex_position = apply_skin_pose(vertex, vertex.position) * transform.scale;
+    out.vertex_position = instance_position(transform, apply_skin_pose(vertex, vertex.position));
     let pos = vec4<f32>(out.vertex_position, 1.0);
     out.position = camera.projection * pos;
     out.position_sun_space = sun.biased_projection * pos;
-    out.normal = normalize(apply_skin_rotation(vertex, vertex.normal));
+    out.normal = instance_normal(transform, apply_skin_rotation(vertex, vertex_normal(vertex)));
+    let tangent = vertex_tangent(vertex);
+    out.tangent = instance_tangent(transform, vec4<f32>(apply_skin_rotation(vertex, tangent.xyz), tangent.w));
     return out;
 }}
 
@@ -95,7 +93,11 @@ fn vs_main(vertex: Vertex, transform: Transform) -> Output {{
 var diffuse_texture: texture_2d<f32>;
 @group(1) @binding(1)
 var diffuse_texture_sampler: sampler;
-
+@group(1) @binding(2)
+var normal_texture: texture_2d<f32>;
+@group(1) @binding(3)
+var normal_texture_sampler: sampler;
+{normal_map}
 @group(3) @binding(1)
 var sun_texture: texture_2d<f32>;
 @group(3) @binding(2)
@@ -111,6 +113,7 @@ fn get_closest_depth(x: f32, y: f32) -> f32 {{
 @fragment
 fn fs_main(in: Output) -> @location(0) vec4<f32> {{
     let texture = textureSample(diffuse_texture, diffuse_texture_sampler, in.uv);
+    let normal = mapped_normal(in.normal, in.tangent, textureSample(normal_texture, normal_texture_sampler, in.uv).xyz);
     let closest_depth = (
         get_closest_depth(in.position_sun_space.x      , in.position_sun_space.y      ) +
         get_closest_depth(in.position_sun_space.x + {a}, in.position_sun_space.y      ) +
@@ -122,9 +125,9 @@ fn fs_main(in: Output) -> @location(0) vec4<f32> {{
         get_closest_depth(in.position_sun_space.x + {a}, in.position_sun_space.y - {a}) +
         get_closest_depth(in.position_sun_space.x - {a}, in.position_sun_space.y + {a})
     ) / 9.0;
-    let ndotl = dot(in.normal, sun.direction.xyz);
+    let ndotl = dot(normal, sun.direction.xyz);
     let view_dir = normalize(camera.position.xyz - in.vertex_position);
-    let reflect_dir = reflect(-sun.direction.xyz, in.normal);
+    let reflect_dir = reflect(-sun.direction.xyz, normal);
 
     let bias = clamp(tan(acos( ndotl*0.5+0.5 )), 0., 0.01);
     var light = 1.0;
@@ -133,36 +136,34 @@ fn fs_main(in: Output) -> @location(0) vec4<f32> {{
     let specular = dot(view_dir, reflect_dir);
     if(specular > 0.5){{ light += 0.1; }}
 
-    let rim = 1.0 - dot(view_dir, in.normal);
+    let rim = 1.0 - dot(view_dir, normal);
     if(rim > 0.6){{ light += 0.2; }}
 
     return texture * light;
 }}
-").into())
+", vertex = super::vertex_wgsl(vertex_type), instance = super::INSTANCE_WGSL, normal_map = super::NORMAL_MAP_WGSL).into())
         });
         let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Basic animation shader render pipeline layout"),
             bind_group_layouts: &[
                 &crate::camera::bind_group_layout(device),
-                &crate::assets::texture_bind_group(device),
+                &super::material_bind_group_layout(device),
                 &crate::assets::armature_bind_group_layout(device),
                 &directional_light_bind_group_layout(device)
             ],
             push_constant_ranges: &[]
         });
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let create_pipeline = |vertex_type: VertexType| {
+            let shader = shader(vertex_type);
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Basic animation shader render pipeline"),
             layout: Some(&render_pipeline_layout),
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: "vs_main",
                 buffers: &[
-                    crate::assets::vertex::VertexNUS::LAYOUT,
-                    wgpu::VertexBufferLayout {
-                        array_stride: std::mem::size_of::<InstanceTransform>() as wgpu::BufferAddress,
-                        step_mode: wgpu::VertexStepMode::Instance,
-                        attributes: &wgpu::vertex_attr_array![5 => Float32x3, 6 => Float32x3]
-                    }
+                    crate::assets::vertex::layout(vertex_type),
+                    InstanceTransform::LAYOUT
                 ]
             },
             fragment: Some(wgpu::FragmentState {
@@ -196,9 +197,14 @@ fn fs_main(in: Output) -> @location(0) vec4<f32> {{
                 alpha_to_coverage_enabled: false
             },
             multiview: None
-        });
+        })
+        };
         Self {
-            render_pipeline
+            render_pipelines: VERTEX_TYPES.iter().map(|vertex_type| (*vertex_type, create_pipeline(*vertex_type))).collect()
         }
     }
+    /// Pipeline reading the vertex buffers of `vertex_type`, None when the shader does not read them
+    pub fn pipeline(&self, vertex_type: VertexType) -> Option<&wgpu::RenderPipeline> {
+        super::find_pipeline(&self.render_pipelines, vertex_type)
+    }
 }
\ No newline at end of file
@@ -1,20 +1,169 @@
+use std::sync::Arc;
+
+use crate::{assets::{Texture, VertexType}, context::Context};
+
 pub mod basic_anim;
 pub mod terrain;
 
+#[derive(Clone)]
 pub enum Material {
     BasicAnim(basic_anim::Material),
     Terrain(terrain::Material)
 }
+impl Material {
+    /// Material of the shader drawing `vertex_type` vertices, None when no shader reads them
+    pub fn for_vertex_type(c: &Context, vertex_type: VertexType, texture: Arc<Texture>, normal: Option<Arc<Texture>>) -> Option<Self> {
+        match vertex_type {
+            v if basic_anim::VERTEX_TYPES.contains(&v) => Some(basic_anim::Material::new(c, texture, normal)),
+            v if terrain::VERTEX_TYPES.contains(&v) => Some(terrain::Material::new(c, texture, normal)),
+            _ => None
+        }
+    }
+    pub fn texture(&self) -> &Arc<Texture> {
+        match self {
+            Self::BasicAnim(material) => &material.texture,
+            Self::Terrain(material) => &material.texture
+        }
+    }
+    /// None when the material keeps the vertex normals
+    pub fn normal(&self) -> Option<&Arc<Texture>> {
+        match self {
+            Self::BasicAnim(material) => material.normal.as_ref(),
+            Self::Terrain(material) => material.normal.as_ref()
+        }
+    }
+    /// Vertices the shader of the material reads, the first ones are drawn when a mesh has none of them
+    pub fn vertex_types(&self) -> &'static [VertexType] {
+        match self {
+            Self::BasicAnim(_) => &basic_anim::VERTEX_TYPES,
+            Self::Terrain(_) => &terrain::VERTEX_TYPES
+        }
+    }
+    /// The same material sampling other textures
+    pub fn with_textures(&self, c: &Context, texture: Arc<Texture>, normal: Option<Arc<Texture>>) -> Self {
+        match self {
+            Self::BasicAnim(_) => basic_anim::Material::new(c, texture, normal),
+            Self::Terrain(_) => terrain::Material::new(c, texture, normal)
+        }
+    }
+}
+
+/// Diffuse texture at bindings 0 and 1, normal map at 2 and 3
+/// Pipeline of a shader reading the vertex buffers of `vertex_type`, `Context::add_object` draws a cube
+/// in place of meshes the shader of their material has no pipeline for
+pub fn find_pipeline(pipelines: &[(VertexType, wgpu::RenderPipeline)], vertex_type: VertexType) -> Option<&wgpu::RenderPipeline> {
+    pipelines.iter().find(|(v, _)| *v == vertex_type).map(|(_, pipeline)| pipeline)
+}
+
+pub fn material_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    let texture = |binding| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            multisampled: false,
+            view_dimension: wgpu::TextureViewDimension::D2,
+            sample_type: wgpu::TextureSampleType::Float { filterable: true }
+        },
+        count: None
+    };
+    let sampler = |binding| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+        count: None
+    };
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("material_bind_group_layout"),
+        entries: &[texture(0), sampler(1), texture(2), sampler(3)]
+    })
+}
+/// Binds a material's textures, the flat normal map stands in for a missing one
+pub fn material_bind_group(c: &Context, texture: &Texture, normal: Option<&Texture>) -> wgpu::BindGroup {
+    let normal = normal.unwrap_or(&c.shaders.flat_normal);
+    c.device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some(texture.name.as_str()),
+        layout: &material_bind_group_layout(&c.device),
+        entries: &[
+            wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&texture.view) },
+            wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&texture.sampler) },
+            wgpu::BindGroupEntry { binding: 2, resource: wgpu::BindingResource::TextureView(&normal.view) },
+            wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::Sampler(&normal.sampler) }
+        ]
+    })
+}
+
+/// `struct Vertex` with the attributes of `vertex_type`, plus `vertex_normal`, which unfolds octahedral normals,
+/// and `vertex_tangent`, zero for vertices without tangents so normal maps leave their normals as they are
+pub fn vertex_wgsl(vertex_type: VertexType) -> String {
+    let quantized = vertex_type.is_quantized();
+    let mut fields = vec!["@location(0) position: vec3<f32>"];
+    fields.push(if quantized { "@location(1) normal: vec2<f32>" } else { "@location(1) normal: vec3<f32>" });
+    fields.push("@location(2) uv: vec2<f32>");
+    if vertex_type.has_joints() {
+        fields.push("@location(3) joints: vec4<u32>");
+        fields.push("@location(4) weights: vec4<f32>")
+    }
+    if vertex_type.has_tangents() {
+        fields.push("@location(7) tangent: vec4<f32>")
+    }
+    let normal = if quantized { "
+    let n = vec3<f32>(vertex.normal, 1.0 - abs(vertex.normal.x) - abs(vertex.normal.y));
+    let t = max(-n.z, 0.0);
+    return normalize(vec3<f32>(n.xy + select(vec2<f32>(t), vec2<f32>(-t), n.xy >= vec2<f32>(0.0)), n.z));"
+    } else { "
+    return vertex.normal;"
+    };
+    let tangent = if vertex_type.has_tangents() { "vertex.tangent" } else { "vec4<f32>(0.0)" };
+    format!("struct Vertex {{\n    {}\n}};
+fn vertex_normal(vertex: Vertex) -> vec3<f32> {{{}\n}}
+fn vertex_tangent(vertex: Vertex) -> vec4<f32> {{\n    return {};\n}}
+", fields.join(",\n    "), normal, tangent)
+}
+
+/// Normal of a fragment, a zero tangent keeps the interpolated normal
+pub const NORMAL_MAP_WGSL: &str = "
+fn mapped_normal(normal: vec3<f32>, tangent: vec4<f32>, sample: vec3<f32>) -> vec3<f32> {
+    let n = normalize(normal);
+    let t = select(vec3<f32>(0.0), normalize(tangent.xyz - n * dot(n, tangent.xyz)), dot(tangent.xyz, tangent.xyz) > 0.0);
+    let b = cross(n, t) * tangent.w;
+    let m = sample * 2.0 - 1.0;
+    return normalize(t * m.x + b * m.y + n * m.z);
+}
+";
+
+/// Instance attributes of `InstanceTransform` and the functions placing a vertex with them
+pub const INSTANCE_WGSL: &str = "
+struct Transform {
+    @location(5) position: vec3<f32>,
+    @location(6) scale: vec3<f32>,
+    @location(8) rotation: vec4<f32>
+};
+fn rotate(q: vec4<f32>, v: vec3<f32>) -> vec3<f32> {
+    return v + 2.0 * cross(q.xyz, cross(q.xyz, v) + q.w * v);
+}
+fn instance_position(transform: Transform, position: vec3<f32>) -> vec3<f32> {
+    return transform.position + rotate(transform.rotation, position * transform.scale);
+}
+fn instance_normal(transform: Transform, normal: vec3<f32>) -> vec3<f32> {
+    return normalize(rotate(transform.rotation, normal / transform.scale));
+}
+fn instance_tangent(transform: Transform, tangent: vec4<f32>) -> vec4<f32> {
+    return vec4<f32>(rotate(transform.rotation, tangent.xyz * transform.scale), tangent.w);
+}
+";
 
 pub struct Shaders {
     pub basic_anim: basic_anim::Shader,
-    pub terrain: terrain::Shader
+    pub terrain: terrain::Shader,
+    /// normal map of the materials without one
+    pub flat_normal: Texture
 }
 impl Shaders {
-    pub fn new(device: &wgpu::Device, surface_texture_format: wgpu::TextureFormat) -> Self {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, surface_texture_format: wgpu::TextureFormat) -> Self {
         Self {
             basic_anim: basic_anim::Shader::new(device, surface_texture_format),
-            terrain: terrain::Shader::new(device, surface_texture_format)
+            terrain: terrain::Shader::new(device, surface_texture_format),
+            flat_normal: Texture::flat_normal(device, queue)
         }
     }
-}
\ No newline at end of file
+}
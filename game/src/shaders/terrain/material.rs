@@ -1,13 +1,19 @@
 use std::sync::Arc;
-use crate::assets::Texture;
+use crate::{assets::Texture, context::Context};
 
+#[derive(Clone)]
 pub struct Material {
-    pub texture: Arc<Texture>
+    pub texture: Arc<Texture>,
+    pub normal: Option<Arc<Texture>>,
+    pub bind_group: Arc<wgpu::BindGroup>
 }
 impl Material {
-    pub fn new(texture: Arc<Texture>) -> crate::shaders::Material {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(c: &Context, texture: Arc<Texture>, normal: Option<Arc<Texture>>) -> crate::shaders::Material {
         crate::shaders::Material::Terrain(Self {
-            texture
+            bind_group: Arc::new(crate::shaders::material_bind_group(c, &texture, normal.as_deref())),
+            texture,
+            normal
         })
     }
-}
\ No newline at end of file
+}
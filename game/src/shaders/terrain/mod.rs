@@ -1,33 +1,42 @@
 mod material;
 pub use material::Material;
 
-use crate::{assets::DEPTH_FORMAT, light::directional::directional_light_bind_group_layout};
+use crate::{assets::{InstanceTransform, VertexLayout, DEPTH_FORMAT, VertexType}, light::directional::directional_light_bind_group_layout};
+
+/// Static vertices, with or without tangents
+pub const VERTEX_TYPES: [VertexType; 3] = [VertexType::NU, VertexType::NUQ, VertexType::NUT];
 
 pub struct Shader {
-    pub render_pipeline: wgpu::RenderPipeline
+    /// one for every vertex type the shader reads
+    pub render_pipelines: Vec<(VertexType, wgpu::RenderPipeline)>
 }
 
 impl Shader {
     pub fn new(device: &wgpu::Device, surface_texture_format: wgpu::TextureFormat) -> Self {
         log::info!("Creating terrain shader");
-        let shader = device.create_shader_module(wgpu::include_wgsl!("./shader.wgsl").into());
         let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Terrain shader render pipeline layout"),
             bind_group_layouts: &[
                 &crate::camera::bind_group_layout(device),
-                &crate::assets::texture_bind_group(device),
+                &super::material_bind_group_layout(device),
                 &directional_light_bind_group_layout(device)
             ],
             push_constant_ranges: &[]
         });
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        let create_pipeline = |vertex_type: VertexType| {
+            let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(format!("{}{}{}{}", super::vertex_wgsl(vertex_type), super::INSTANCE_WGSL, super::NORMAL_MAP_WGSL, include_str!("./shader.wgsl")).into())
+            });
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Terrain shader render pipeline"),
             layout: Some(&render_pipeline_layout),
             vertex: wgpu::VertexState {
                 module: &shader,
                 entry_point: "vs_main",
                 buffers: &[
-                    crate::assets::vertex::VertexNU::LAYOUT
+                    crate::assets::vertex::layout(vertex_type),
+                    InstanceTransform::LAYOUT
                 ]
             },
             fragment: Some(wgpu::FragmentState {
@@ -61,9 +70,14 @@ impl Shader {
                 alpha_to_coverage_enabled: false
             },
             multiview: None
-        });
+        })
+        };
         Self {
-            render_pipeline
+            render_pipelines: VERTEX_TYPES.iter().map(|vertex_type| (*vertex_type, create_pipeline(*vertex_type))).collect()
         }
     }
+    /// Pipeline reading the vertex buffers of `vertex_type`, None when the shader does not read them
+    pub fn pipeline(&self, vertex_type: VertexType) -> Option<&wgpu::RenderPipeline> {
+        super::find_pipeline(&self.render_pipelines, vertex_type)
+    }
 }
\ No newline at end of file
@@ -1,9 +1,3 @@
-struct Vertex {
-    @location(0) position: vec3<f32>,
-    @location(1) normal: vec3<f32>,
-    @location(2) uv: vec2<f32>
-};
-
 struct Camera {
     @location(0) projection: mat4x4<f32>,
     @location(1) position: vec4<f32>
@@ -23,17 +17,19 @@ struct Output {
     @builtin(position) position: vec4<f32>,
     @location(0) position_sun_space: vec4<f32>,
     @location(1) normal: vec3<f32>,
-    @location(2) uv: vec2<f32>
+    @location(2) uv: vec2<f32>,
+    @location(3) tangent: vec4<f32>
 };
 
 @vertex
-fn vs_main(vertex: Vertex) -> Output {
+fn vs_main(vertex: Vertex, transform: Transform) -> Output {
     var out: Output;
     out.uv = vertex.uv;
-    let pos = vec4<f32>(vertex.position, 1.0);
+    let pos = vec4<f32>(instance_position(transform, vertex.position), 1.0);
     out.position = camera.projection * pos;
     out.position_sun_space = sun.biased_projection * pos;
-    out.normal = vertex.normal;
+    out.normal = instance_normal(transform, vertex_normal(vertex));
+    out.tangent = instance_tangent(transform, vertex_tangent(vertex));
     return out;
 }
 
@@ -41,6 +37,10 @@ fn vs_main(vertex: Vertex) -> Output {
 var diffuse_texture: texture_2d<f32>;
 @group(1)@binding(1)
 var diffuse_texture_sampler: sampler;
+@group(1) @binding(2)
+var normal_texture: texture_2d<f32>;
+@group(1) @binding(3)
+var normal_texture_sampler: sampler;
 
 @group(2) @binding(1)
 var sun_texture: texture_2d<f32>;
@@ -50,12 +50,14 @@ var sun_texture_sampler: sampler;
 @fragment
 fn fs_main(in: Output) -> @location(0) vec4<f32> {
     let texture = textureSample(diffuse_texture, diffuse_texture_sampler, in.uv);
+    let normal = mapped_normal(in.normal, in.tangent, textureSample(normal_texture, normal_texture_sampler, in.uv).xyz);
     //shadow
     let closest_depth = textureSample(sun_texture, sun_texture_sampler, vec2<f32>(
         in.position_sun_space.x * 0.5 + 0.5,
         in.position_sun_space.y * -0.5 + 0.5
     )).r;
-    let bias = clamp(tan(acos( dot(in.normal,sun.direction.xyz)*0.5+0.5 )), 0., 0.01);
+    let ndotl = dot(normal, sun.direction.xyz);
+    let bias = clamp(tan(acos( ndotl*0.5+0.5 )), 0., 0.01);
     var shadow = 1.0;
     if(closest_depth < in.position_sun_space.z - bias){{ shadow = 0.85; }}
     return texture * shadow;
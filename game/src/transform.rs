@@ -1,5 +1,6 @@
 use cgmath::Matrix4;
-use crate::{utils::{rotation_to_mat4, scale_to_mat4}, assets::Reader};
+use pack::JointPose;
+use crate::utils::{rotation_to_mat4, scale_to_mat4};
 
 #[derive(Clone, Copy)]
 pub struct Transform {
@@ -8,16 +9,18 @@ pub struct Transform {
     pub scale: [f32;3]
 }
 impl Transform {
-    pub fn read(reader: &mut Reader) -> Self {
-        Self {
-            translation: reader.read_vec3(),
-            rotation: reader.read_vec3(),
-            scale: reader.read_vec3()
-        }
-    }
     pub fn mat(&self) -> Matrix4<f32> {
         Matrix4::from_translation(self.translation.into()) *
         rotation_to_mat4(self.rotation) *
         scale_to_mat4(self.scale)
     }
+}
+impl From<JointPose> for Transform {
+    fn from(pose: JointPose) -> Self {
+        Self {
+            translation: pose.translation,
+            rotation: pose.rotation,
+            scale: pose.scale
+        }
+    }
 }
\ No newline at end of file
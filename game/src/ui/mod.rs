@@ -4,6 +4,7 @@ mod square;  pub use square::*;
 
 use crate::{assets::Texture, context::Context};
 
+#[allow(dead_code)]
 pub struct UI {
     pub depth_texture: Texture,
     pub square_shader: wgpu::RenderPipeline,
@@ -20,7 +21,7 @@ impl UI {
     pub fn draw<'r, 's: 'r>(
         render_pass: &mut wgpu::RenderPass<'r>,
         c: &'s Context,
-        squares: &'s Vec<Arc<Square>>
+        squares: &'s [Arc<Square>]
     ) {
         {
             render_pass.set_pipeline(&c.ui.square_shader);
@@ -41,8 +42,8 @@ pub enum UIElementTexture {
     Owned(Arc<Texture>),
     SunDepthBuffer
 }
-impl Into<UIElementTexture> for Arc<Texture> {
-    fn into(self) -> UIElementTexture {
-        UIElementTexture::Owned(self)
+impl From<Arc<Texture>> for UIElementTexture {
+    fn from(texture: Arc<Texture>) -> Self {
+        UIElementTexture::Owned(texture)
     }
 }
\ No newline at end of file
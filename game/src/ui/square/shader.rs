@@ -2,7 +2,7 @@ use wgpu::BlendComponent;
 
 pub fn new(device: &wgpu::Device, surface_format: wgpu::TextureFormat) -> wgpu::RenderPipeline {
     log::info!("Creating square shader");
-    let shader = device.create_shader_module(wgpu::include_wgsl!("./shader.wgsl").into());
+    let shader = device.create_shader_module(wgpu::include_wgsl!("./shader.wgsl"));
     let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
         label: Some("square shader render pipeline layout"),
         bind_group_layouts: &[
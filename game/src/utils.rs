@@ -8,7 +8,7 @@ use crate::settings::Settings;
 pub fn create_adapter(instance: &wgpu::Instance, surface: &wgpu::Surface) -> wgpu::Adapter {
     block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
         power_preference: wgpu::PowerPreference::HighPerformance,
-        compatible_surface: Some(&surface),
+        compatible_surface: Some(surface),
         force_fallback_adapter: false
     })).unwrap();
     instance.enumerate_adapters(wgpu::Backends::all()).next().unwrap()
@@ -17,7 +17,8 @@ pub fn create_adapter(instance: &wgpu::Instance, surface: &wgpu::Surface) -> wgp
 pub fn create_device_queue(adapter: &wgpu::Adapter) -> (wgpu::Device, wgpu::Queue) {
     block_on(adapter.request_device(
         &wgpu::DeviceDescriptor {
-            features: wgpu::Features::empty(),
+            // block compressed textures are decompressed at load time when this is missing
+            features: adapter.features() & wgpu::Features::TEXTURE_COMPRESSION_BC,
             limits: wgpu::Limits::default(),
             label: None
         },
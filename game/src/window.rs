@@ -1,12 +1,14 @@
 use winit::{window::{WindowBuilder, Fullscreen, Window}, event_loop::EventLoop, dpi::PhysicalPosition};
 use crate::settings::Settings;
 
+pub const TITLE: &str = "Experimental fragment";
+
 pub fn new(settings: &Settings, event_loop: &EventLoop<()>) -> Window {
     let w = WindowBuilder::new()
-        .with_title("Experimental fragment")
+        .with_title(TITLE)
         .with_resizable(true)
         .with_decorations(settings.window_decorations)
-        .build(&event_loop).unwrap();
+        .build(event_loop).unwrap();
     if settings.window_fullscreen {
         let monitor = w.current_monitor().unwrap();
         w.set_fullscreen(Some(Fullscreen::Exclusive({
@@ -18,7 +20,7 @@ pub fn new(settings: &Settings, event_loop: &EventLoop<()>) -> Window {
                     }
                 }
             }
-            if let None = r {
+            if r.is_none() {
                 let monitor_size = monitor.size();
                 warn!("Size \"{:?}\" not supported, switching to monitor size: {monitor_size:?}", settings.window_size);
                 for video_mode in monitor.video_modes() {
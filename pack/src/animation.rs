@@ -0,0 +1,56 @@
+use crate::{Reader, Writer, AssetError};
+
+/// Armature space transform of a joint, the rotation is an euler angle applied as z * y * x
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JointPose {
+    pub translation: [f32;3],
+    pub rotation: [f32;3],
+    pub scale: [f32;3]
+}
+
+/// Poses of every joint of an armature, sampled at a fixed rate. compiler/compile.py writes the same layout from blender
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationRecord {
+    pub name: String,
+    pub joints_length: u16,
+    /// every frame holds one pose per joint
+    pub frames: Vec<Vec<JointPose>>
+}
+impl AnimationRecord {
+    pub const KIND: u8 = b'A';
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut res = Writer(Vec::new());
+        res.append_header(Self::KIND, &self.name);
+        res.append_u16(self.joints_length);
+        res.append_u32(self.frames.len() as u32);
+        for frame in self.frames.iter() {
+            for pose in frame.iter() {
+                res.append_vec3_f32(pose.translation);
+                res.append_vec3_f32(pose.rotation);
+                res.append_vec3_f32(pose.scale);
+            }
+        }
+        res.append_bytes(b"END");
+        res.0
+    }
+    /// Reads the fields that follow the record header
+    pub fn decode(name: String, reader: &mut Reader) -> Result<Self, AssetError> {
+        let joints_length = reader.read_u16()?;
+        let frames_length = reader.read_u32()? as usize;
+        let mut frames = Vec::new();
+        for _ in 0..frames_length {
+            let mut frame = Vec::with_capacity(joints_length as usize);
+            for _ in 0..joints_length {
+                frame.push(JointPose {
+                    translation: reader.read_vec3()?,
+                    rotation: reader.read_vec3()?,
+                    scale: reader.read_vec3()?
+                });
+            }
+            frames.push(frame);
+        }
+        reader.read_end(&name)?;
+        Ok(Self { name, joints_length, frames })
+    }
+}
@@ -0,0 +1,133 @@
+//! BC1 (DXT1) block compression, blocks are stored the way the GPU reads them: little endian
+
+/// Compresses an rgba image, the alpha channel is dropped
+pub fn encode_bc1(rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let mut res = Vec::with_capacity(width.div_ceil(4) * height.div_ceil(4) * 8);
+    for block_y in (0..height).step_by(4) {
+        for block_x in (0..width).step_by(4) {
+            let mut block = [[0f32;3];16];
+            for (i, pixel) in block.iter_mut().enumerate() {
+                // blocks crossing the border of the image repeat its last row and column
+                let x = (block_x + i % 4).min(width - 1);
+                let y = (block_y + i / 4).min(height - 1);
+                let p = &rgba[(y * width + x) * 4..];
+                *pixel = [p[0] as f32, p[1] as f32, p[2] as f32];
+            }
+            res.extend_from_slice(&encode_block(&block));
+        }
+    }
+    res
+}
+
+/// Decompresses BC1 blocks into an rgba image
+pub fn decode_bc1(blocks: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let mut res = vec![0; width * height * 4];
+    let blocks_x = width.div_ceil(4);
+    for (block_id, block) in blocks.chunks_exact(8).enumerate() {
+        let (block_x, block_y) = (block_id % blocks_x * 4, block_id / blocks_x * 4);
+        let c0 = u16::from_le_bytes([block[0], block[1]]);
+        let c1 = u16::from_le_bytes([block[2], block[3]]);
+        let palette = palette(c0, c1, c0 > c1);
+        let indices = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+        for i in 0..16 {
+            let (x, y) = (block_x + i % 4, block_y + i / 4);
+            if x >= width || y >= height { continue }
+            let color = palette[(indices >> (i * 2)) as usize & 3];
+            res[(y * width + x) * 4..][..4].copy_from_slice(&color);
+        }
+    }
+    res
+}
+
+pub(crate) fn encode_block(block: &[[f32;3];16]) -> [u8;8] {
+    let (max, min) = endpoints(block);
+    let (mut c0, mut c1) = (to_565(max), to_565(min));
+    if c0 < c1 {
+        std::mem::swap(&mut c0, &mut c1)
+    }
+    let mut indices = 0u32;
+    if c0 != c1 {
+        let palette = palette(c0, c1, true);
+        for (i, pixel) in block.iter().enumerate() {
+            let mut best = (f32::MAX, 0);
+            for (id, color) in palette.iter().enumerate() {
+                let d: f32 = (0..3).map(|c| (pixel[c] - color[c] as f32).powi(2)).sum();
+                if d < best.0 { best = (d, id) }
+            }
+            indices |= (best.1 as u32) << (i * 2);
+        }
+    }
+    let mut res = [0;8];
+    res[0..2].copy_from_slice(&c0.to_le_bytes());
+    res[2..4].copy_from_slice(&c1.to_le_bytes());
+    res[4..8].copy_from_slice(&indices.to_le_bytes());
+    res
+}
+
+/// Extremes of the block along its principal axis, found with a few power iterations
+fn endpoints(block: &[[f32;3];16]) -> ([f32;3], [f32;3]) {
+    let mut mean = [0f32;3];
+    for pixel in block.iter() {
+        for c in 0..3 { mean[c] += pixel[c] / 16. }
+    }
+    let mut covariance = [[0f32;3];3];
+    for pixel in block.iter() {
+        for a in 0..3 {
+            for b in 0..3 {
+                covariance[a][b] += (pixel[a] - mean[a]) * (pixel[b] - mean[b]);
+            }
+        }
+    }
+    // starting from the channel that varies the most keeps the iteration away from a zero vector
+    let channel = (0..3).fold(0, |best, c| if covariance[c][c] > covariance[best][best] { c } else { best });
+    let mut axis = covariance[channel];
+    for _ in 0..4 {
+        let next: Vec<f32> = (0..3).map(|a| (0..3).map(|b| covariance[a][b] * axis[b]).sum()).collect();
+        let len = next.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if len < f32::EPSILON { break }
+        axis = [next[0] / len, next[1] / len, next[2] / len];
+    }
+    let project = |pixel: &[f32;3]| (0..3).map(|c| (pixel[c] - mean[c]) * axis[c]).sum::<f32>();
+    let mut max = (f32::MIN, block[0]);
+    let mut min = (f32::MAX, block[0]);
+    for pixel in block.iter() {
+        let d = project(pixel);
+        if d > max.0 { max = (d, *pixel) }
+        if d < min.0 { min = (d, *pixel) }
+    }
+    (max.1, min.1)
+}
+
+fn to_565(color: [f32;3]) -> u16 {
+    let r = (color[0].clamp(0., 255.) * 31. / 255.).round() as u16;
+    let g = (color[1].clamp(0., 255.) * 63. / 255.).round() as u16;
+    let b = (color[2].clamp(0., 255.) * 31. / 255.).round() as u16;
+    (r << 11) | (g << 5) | b
+}
+
+fn from_565(color: u16) -> [u8;4] {
+    let r = (color >> 11) & 31;
+    let g = (color >> 5) & 63;
+    let b = color & 31;
+    [(r << 3 | r >> 2) as u8, (g << 2 | g >> 4) as u8, (b << 3 | b >> 2) as u8, 255]
+}
+
+/// Colors a block can pick from, BC1 blocks with `c0 <= c1` have three colors and a transparent black,
+/// the color blocks of BC3 always have four
+pub(crate) fn palette(c0: u16, c1: u16, four_colors: bool) -> [[u8;4];4] {
+    let (a, b) = (from_565(c0), from_565(c1));
+    let mix = |wa: u16, wb: u16, d: u16| -> [u8;4] {
+        let mut res = [255;4];
+        for c in 0..3 {
+            res[c] = ((a[c] as u16 * wa + b[c] as u16 * wb) / d) as u8;
+        }
+        res
+    };
+    if four_colors {
+        [a, b, mix(2, 1, 3), mix(1, 2, 3)]
+    } else {
+        [a, b, mix(1, 1, 2), [0;4]]
+    }
+}
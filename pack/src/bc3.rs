@@ -0,0 +1,82 @@
+//! BC3 (DXT5) block compression: an alpha block followed by a BC1 color block, little endian like BC1
+
+use crate::bc1::{encode_block, palette};
+
+/// Compresses an rgba image, keeping its alpha channel
+pub fn encode_bc3(rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let mut res = Vec::with_capacity(width.div_ceil(4) * height.div_ceil(4) * 16);
+    for block_y in (0..height).step_by(4) {
+        for block_x in (0..width).step_by(4) {
+            let mut block = [[0f32;3];16];
+            let mut alpha = [0u8;16];
+            for i in 0..16 {
+                // blocks crossing the border of the image repeat its last row and column
+                let x = (block_x + i % 4).min(width - 1);
+                let y = (block_y + i / 4).min(height - 1);
+                let p = &rgba[(y * width + x) * 4..];
+                block[i] = [p[0] as f32, p[1] as f32, p[2] as f32];
+                alpha[i] = p[3];
+            }
+            res.extend_from_slice(&encode_alpha(&alpha));
+            res.extend_from_slice(&encode_block(&block));
+        }
+    }
+    res
+}
+
+/// Decompresses BC3 blocks into an rgba image
+pub fn decode_bc3(blocks: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let mut res = vec![0; width * height * 4];
+    let blocks_x = width.div_ceil(4);
+    for (block_id, block) in blocks.chunks_exact(16).enumerate() {
+        let (block_x, block_y) = (block_id % blocks_x * 4, block_id / blocks_x * 4);
+        let alphas = alpha_palette(block[0], block[1]);
+        let alpha_indices = u64::from_le_bytes([block[2], block[3], block[4], block[5], block[6], block[7], 0, 0]);
+        let colors = palette(u16::from_le_bytes([block[8], block[9]]), u16::from_le_bytes([block[10], block[11]]), true);
+        let indices = u32::from_le_bytes([block[12], block[13], block[14], block[15]]);
+        for i in 0..16 {
+            let (x, y) = (block_x + i % 4, block_y + i / 4);
+            if x >= width || y >= height { continue }
+            let pixel = &mut res[(y * width + x) * 4..][..4];
+            pixel.copy_from_slice(&colors[(indices >> (i * 2)) as usize & 3]);
+            pixel[3] = alphas[(alpha_indices >> (i * 3)) as usize & 7];
+        }
+    }
+    res
+}
+
+/// Both alpha endpoints and a 3 bit index per pixel, always in the mode of 8 interpolated values
+fn encode_alpha(alpha: &[u8;16]) -> [u8;8] {
+    let (a0, a1) = (*alpha.iter().max().unwrap(), *alpha.iter().min().unwrap());
+    let mut indices = 0u64;
+    if a0 != a1 {
+        let palette = alpha_palette(a0, a1);
+        for (i, a) in alpha.iter().enumerate() {
+            let best = (0..8).min_by_key(|id| (palette[*id] as i16 - *a as i16).abs()).unwrap();
+            indices |= (best as u64) << (i * 3);
+        }
+    }
+    let mut res = [0;8];
+    res[0] = a0;
+    res[1] = a1;
+    res[2..8].copy_from_slice(&indices.to_le_bytes()[..6]);
+    res
+}
+
+/// Alpha values a block can pick from, a block with `a0 <= a1` has six values, a transparent and an opaque one
+fn alpha_palette(a0: u8, a1: u8) -> [u8;8] {
+    let (a, b) = (a0 as u16, a1 as u16);
+    let mut res = [a0, a1, 0, 0, 0, 0, 0, 255];
+    if a0 > a1 {
+        for i in 1..7 {
+            res[i + 1] = ((a * (7 - i as u16) + b * i as u16) / 7) as u8;
+        }
+    } else {
+        for i in 1..5 {
+            res[i + 1] = ((a * (5 - i as u16) + b * i as u16) / 5) as u8;
+        }
+    }
+    res
+}
@@ -0,0 +1,83 @@
+use crate::{Reader, Writer, AssetError};
+
+/// Axis aligned box and sphere around a set of points, the sphere is centered on the box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: [f32;3],
+    pub max: [f32;3],
+    pub center: [f32;3],
+    pub radius: f32
+}
+impl Bounds {
+    /// Bounds of no point, the bounds of joints no vertex depends on
+    pub const EMPTY: Self = Self { min: [f32::INFINITY;3], max: [f32::NEG_INFINITY;3], center: [0.;3], radius: -1. };
+
+    /// The sphere reaches the farthest point from the center of the box, it is never larger than the box
+    pub fn from_points(points: impl Iterator<Item = [f32;3]> + Clone) -> Self {
+        let (mut min, mut max) = (Self::EMPTY.min, Self::EMPTY.max);
+        for point in points.clone() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(point[axis]);
+                max[axis] = max[axis].max(point[axis]);
+            }
+        }
+        if min[0] > max[0] { return Self::EMPTY }
+        let center = [0, 1, 2].map(|axis| (min[axis] + max[axis]) * 0.5);
+        let radius = points.map(|point| distance(point, center)).fold(0., f32::max);
+        Self { min, max, center, radius }
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.radius < 0.
+    }
+    /// Smallest bounds holding both, the sphere is rebuilt around the new box and can be loose
+    pub fn union(&self, other: &Self) -> Self {
+        if self.is_empty() { return *other }
+        if other.is_empty() { return *self }
+        let min = [0, 1, 2].map(|axis| self.min[axis].min(other.min[axis]));
+        let max = [0, 1, 2].map(|axis| self.max[axis].max(other.max[axis]));
+        let center = [0, 1, 2].map(|axis| (min[axis] + max[axis]) * 0.5);
+        let radius = (distance(self.center, center) + self.radius).max(distance(other.center, center) + other.radius);
+        // the sphere of the box is smaller whenever the spheres are far apart
+        let radius = radius.min(distance(min, center));
+        Self { min, max, center, radius }
+    }
+    /// Bounds of the box moved by a column major matrix, the sphere is scaled by its largest axis
+    pub fn transform(&self, mat: [[f32;4];4]) -> Self {
+        if self.is_empty() { return *self }
+        let corners = (0..8).map(|corner| transform_point(mat, [0, 1, 2].map(|axis| {
+            if corner & (1 << axis) == 0 { self.min[axis] } else { self.max[axis] }
+        })));
+        let mut res = Self::from_points(corners);
+        let scale = mat[..3].iter().map(|axis| distance([axis[0], axis[1], axis[2]], [0.;3])).fold(0., f32::max);
+        let center = transform_point(mat, self.center);
+        res.radius = res.radius.min(distance(center, res.center) + self.radius * scale);
+        res
+    }
+}
+
+/// Transforms a point by a column major matrix
+pub fn transform_point(mat: [[f32;4];4], p: [f32;3]) -> [f32;3] {
+    [0, 1, 2].map(|row| mat[0][row] * p[0] + mat[1][row] * p[1] + mat[2][row] * p[2] + mat[3][row])
+}
+
+#[inline]
+fn distance(a: [f32;3], b: [f32;3]) -> f32 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+}
+
+impl Writer {
+    #[inline]
+    pub fn append_bounds(&mut self, v: Bounds) {
+        self.append_vec3_f32(v.min);
+        self.append_vec3_f32(v.max);
+        self.append_vec3_f32(v.center);
+        self.append_f32(v.radius);
+    }
+}
+impl Reader {
+    #[inline]
+    pub fn read_bounds(&mut self) -> Result<Bounds, AssetError> {
+        Ok(Bounds { min: self.read_vec3()?, max: self.read_vec3()?, center: self.read_vec3()?, radius: self.read_f32()? })
+    }
+}
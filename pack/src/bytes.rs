@@ -0,0 +1,93 @@
+use std::{sync::Arc, ops::{Deref, Range}, path::Path, marker::PhantomData};
+
+enum Storage {
+    Owned(Vec<u8>),
+    Mapped(memmap2::Mmap)
+}
+
+/// Part of a pack read into memory or memory mapped, records keep their vertices and indices in it instead of copying them
+#[derive(Clone)]
+pub struct Bytes {
+    storage: Arc<Storage>,
+    range: Range<usize>
+}
+impl Bytes {
+    /// Maps a whole file.
+    ///
+    /// # Safety
+    /// The file must not be written while it is mapped, the compiler replaces packs with a rename for that reason
+    pub unsafe fn map(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let map = memmap2::Mmap::map(&file)?;
+        Ok(Self { range: 0..map.len(), storage: Arc::new(Storage::Mapped(map)) })
+    }
+    /// Shares a part of these bytes, `range` is relative to them
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        assert!(range.start <= range.end && range.end <= self.range.len(), "slice out of range");
+        Self { storage: self.storage.clone(), range: self.range.start + range.start..self.range.start + range.end }
+    }
+}
+impl From<Vec<u8>> for Bytes {
+    fn from(v: Vec<u8>) -> Self {
+        Self { range: 0..v.len(), storage: Arc::new(Storage::Owned(v)) }
+    }
+}
+impl Deref for Bytes {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        match self.storage.as_ref() {
+            Storage::Owned(v) => &v[self.range.clone()],
+            Storage::Mapped(v) => &v[self.range.clone()]
+        }
+    }
+}
+
+/// Vertices or indices in the layout the GPU reads, owned or pointing into the pack they were read from
+#[derive(Clone)]
+pub enum Slice<T> {
+    Owned(Vec<T>),
+    /// little endian and aligned for `T`, checked by `from_le_bytes`
+    Shared(Bytes, PhantomData<T>)
+}
+impl<T: bytemuck::Pod> Slice<T> {
+    /// Points into `bytes` when they are aligned for `T`, copies them otherwise
+    pub fn from_le_bytes(bytes: Bytes) -> Self {
+        if (bytes.as_ptr() as usize).is_multiple_of(std::mem::align_of::<T>()) {
+            return Self::Shared(bytes, PhantomData)
+        }
+        Self::Owned(bytes.chunks_exact(std::mem::size_of::<T>()).map(bytemuck::pod_read_unaligned).collect())
+    }
+    /// The data as the GPU expects it
+    pub fn bytes(&self) -> &[u8] {
+        bytemuck::cast_slice(self)
+    }
+}
+impl<T: bytemuck::Pod> Deref for Slice<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        match self {
+            Self::Owned(v) => v,
+            Self::Shared(bytes, _) => bytemuck::cast_slice(bytes)
+        }
+    }
+}
+impl<T> From<Vec<T>> for Slice<T> {
+    fn from(v: Vec<T>) -> Self {
+        Self::Owned(v)
+    }
+}
+impl<T> FromIterator<T> for Slice<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::Owned(iter.into_iter().collect())
+    }
+}
+impl<T: bytemuck::Pod + PartialEq> PartialEq for Slice<T> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+impl<T: bytemuck::Pod + std::fmt::Debug> std::fmt::Debug for Slice<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        (**self).fmt(f)
+    }
+}
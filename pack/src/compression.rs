@@ -0,0 +1,141 @@
+use crate::{Reader, Writer, AssetError, RECORD_ALIGNMENT};
+
+/// How the fields of a record are stored, picked per asset in `compile.conf`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Compression {
+    #[default]
+    None,
+    /// best ratio, for packs shipped with the game
+    Zstd,
+    /// fastest to decompress, for large packs loaded often
+    Lz4
+}
+/// zstd level of compressed records, decompression speed does not depend on it
+const ZSTD_LEVEL: i32 = 19;
+/// Largest decompressed fields of a record, the length a corrupted record claims is checked before it is allocated
+const MAX_DECOMPRESSED_LEN: usize = 1 << 30;
+
+impl Compression {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "None",
+            Self::Zstd => "Zstd",
+            Self::Lz4 => "LZ4"
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "None" => Some(Self::None),
+            "Zstd" => Some(Self::Zstd),
+            "LZ4" => Some(Self::Lz4),
+            _ => None
+        }
+    }
+    fn id(&self) -> u8 {
+        match self {
+            Self::None => 0,
+            Self::Zstd => 1,
+            Self::Lz4 => 2
+        }
+    }
+    /// Decompressed bytes one compressed byte can give at most: a zstd block of 128 KB can be a single repeated byte,
+    /// a LZ4 sequence adds at most 255 bytes to a match for each byte of its length
+    fn max_ratio(&self) -> usize {
+        match self {
+            Self::None => 1,
+            Self::Zstd => 1 << 15,
+            Self::Lz4 => 255
+        }
+    }
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Self::None),
+            1 => Some(Self::Zstd),
+            2 => Some(Self::Lz4),
+            _ => None
+        }
+    }
+}
+
+impl Writer {
+    /// Kind, name and compression of a record, the fields that follow are not compressed yet
+    #[inline]
+    pub fn append_header(&mut self, kind: u8, name: impl AsRef<str>) {
+        self.append_u8(kind);
+        self.append_string(name);
+        self.append_u8(Compression::None.id());
+    }
+}
+
+/// Compresses the fields of an encoded record, its kind and name stay readable for the table of contents.
+/// Compressed records store the length of their fields before and after compression
+pub fn compress(record: &[u8], compression: Compression) -> Vec<u8> {
+    let header = record.iter().position(|b| *b == b'#').expect("record without name") + 1;
+    if compression == Compression::None || record.get(header) != Some(&Compression::None.id()) {
+        return record.to_vec()
+    }
+    let fields = &record[header + 1..];
+    let compressed = match compression {
+        Compression::Zstd => zstd::bulk::compress(fields, ZSTD_LEVEL).expect("zstd compression failed"),
+        Compression::Lz4 => lz4_flex::block::compress(fields),
+        Compression::None => unreachable!()
+    };
+    let mut res = Writer(Vec::with_capacity(header + 9 + compressed.len()));
+    res.append_bytes(&record[..header]);
+    res.append_u8(compression.id());
+    res.append_u32(fields.len() as u32);
+    res.append_u32(compressed.len() as u32);
+    res.append_bytes(&compressed);
+    res.0
+}
+
+/// Compression of an encoded record and the length of its fields once decompressed
+pub fn compression(record: &[u8]) -> Option<(Compression, usize)> {
+    let header = record.iter().position(|b| *b == b'#')? + 1;
+    match Compression::from_id(*record.get(header)?)? {
+        Compression::None => Some((Compression::None, record.len() - header - 1)),
+        compression => {
+            let len = record.get(header + 1..header + 5)?;
+            Some((compression, u32::from_be_bytes([len[0], len[1], len[2], len[3]]) as usize))
+        }
+    }
+}
+
+impl Reader {
+    /// Reads the compression of a record, then decodes its fields with `decode`, decompressing them first when needed.
+    /// Decompressed fields keep the alignment they had in the record, so their GPU data is not copied again
+    pub fn read_fields<T>(&mut self, name: String, decode: impl FnOnce(String, &mut Reader) -> Result<T, AssetError>) -> Result<T, AssetError> {
+        let id = self.read_u8()?;
+        let compression = match Compression::from_id(id) {
+            Some(v) => v,
+            None => return Err(AssetError::UnknownCompression { asset: name, compression: id })
+        };
+        if compression == Compression::None {
+            return decode(name, self)
+        }
+        let padding = self.position() % RECORD_ALIGNMENT;
+        let len = self.read_u32()? as usize;
+        let compressed_len = self.read_u32()? as usize;
+        let compressed = self.read_bytes(compressed_len)?;
+        if len > MAX_DECOMPRESSED_LEN || len > compressed_len.saturating_mul(compression.max_ratio()) {
+            return Err(AssetError::Decompression {
+                asset: name,
+                error: format!("it claims {} bytes from {} compressed ones", len, compressed_len)
+            })
+        }
+        let mut fields = vec![0; padding + len];
+        let decompressed = match compression {
+            Compression::Zstd => zstd::bulk::decompress_to_buffer(compressed, &mut fields[padding..]).map_err(|e| e.to_string()),
+            Compression::Lz4 => lz4_flex::block::decompress_into(compressed, &mut fields[padding..]).map_err(|e| e.to_string()),
+            Compression::None => unreachable!()
+        };
+        match decompressed {
+            Ok(v) if v == len => {},
+            Ok(_) => return Err(AssetError::Decompression { asset: name, error: "wrong length".to_string() }),
+            Err(error) => return Err(AssetError::Decompression { asset: name, error })
+        }
+        let mut reader = Reader::from_bytes(fields);
+        reader.seek(padding);
+        decode(name, &mut reader)
+    }
+}
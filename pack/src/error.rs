@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+
+use crate::FORMAT_VERSION;
+
+#[derive(Debug)]
+pub enum AssetError {
+    Io { path: PathBuf, error: std::io::Error },
+    NotAPack { path: PathBuf },
+    UnsupportedVersion { path: PathBuf, version: u32 },
+    UnexpectedEof { offset: usize },
+    BadEndMarker { asset: String, offset: usize },
+    UnknownAssetType { offset: usize, kind: u8 },
+    KindMismatch { asset: String, listed: u8, found: u8 },
+    UnknownVertexType { vertex_type: String },
+    UnknownIndexSize { index_size: u8 },
+    UnknownTextureFormat { format: String },
+    InvalidMipLevels { asset: String, levels: u8 },
+    UnknownColorSpace { asset: String, color_space: u8 },
+    InvalidChannels { asset: String, channels: u8 },
+    UnknownAlphaMode { asset: String, alpha_mode: u8 },
+    InvalidName { offset: usize },
+    LengthMismatch { asset: String },
+    SubMeshOutOfRange { asset: String },
+    NodeOutOfRange { asset: String },
+    TooManyJoints { joints: usize, maximum: usize },
+    UnknownCompression { asset: String, compression: u8 },
+    Decompression { asset: String, error: String }
+}
+impl std::fmt::Display for AssetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, error } => write!(f, "can not read file: {}, error: {}", path.display(), error),
+            Self::NotAPack { path } =>
+                write!(f, "{} is not an asset pack, or was compiled by an older compiler, recompile the assets", path.display()),
+            Self::UnsupportedVersion { path, version } =>
+                write!(f, "{} has format version {}, but this build reads version {}, recompile the assets",
+                    path.display(), version, FORMAT_VERSION),
+            Self::UnexpectedEof { offset } => write!(f, "unexpected end of file at offset {}", offset),
+            Self::BadEndMarker { asset, offset } => write!(f, "asset \"{}\" corrupted, END not found at offset {}", asset, offset),
+            Self::UnknownAssetType { offset, kind } => write!(f, "unknown asset type {} at offset {}", kind, offset),
+            Self::KindMismatch { asset, listed, found } =>
+                write!(f, "asset \"{}\" is listed as type {} but its record has type {}", asset, listed, found),
+            Self::UnknownVertexType { vertex_type } => write!(f, "unknown vertex type: \"{}\"", vertex_type),
+            Self::UnknownIndexSize { index_size } => write!(f, "unknown index size: {} bytes", index_size),
+            Self::UnknownTextureFormat { format } => write!(f, "unknown texture format: \"{}\"", format),
+            Self::InvalidMipLevels { asset, levels } =>
+                write!(f, "texture \"{}\" corrupted, it can not have {} mip levels", asset, levels),
+            Self::UnknownColorSpace { asset, color_space } =>
+                write!(f, "texture \"{}\" has unknown color space {}", asset, color_space),
+            Self::InvalidChannels { asset, channels } =>
+                write!(f, "texture \"{}\" corrupted, it can not have {} channels", asset, channels),
+            Self::UnknownAlphaMode { asset, alpha_mode } =>
+                write!(f, "asset \"{}\" has unknown alpha mode {}", asset, alpha_mode),
+            Self::InvalidName { offset } => write!(f, "invalid UTF-8 name at offset {}", offset),
+            Self::LengthMismatch { asset } =>
+                write!(f, "asset \"{}\" corrupted, record length does not match the table of contents", asset),
+            Self::SubMeshOutOfRange { asset } =>
+                write!(f, "asset \"{}\" corrupted, a sub-mesh is outside of the index buffer", asset),
+            Self::NodeOutOfRange { asset } =>
+                write!(f, "scene \"{}\" corrupted, a node is outside of the scene or before its parent", asset),
+            Self::TooManyJoints { joints, maximum } =>
+                write!(f, "skeleton has {} joints, it can not have more than {}", joints, maximum),
+            Self::UnknownCompression { asset, compression } =>
+                write!(f, "asset \"{}\" has unknown compression {}", asset, compression),
+            Self::Decompression { asset, error } =>
+                write!(f, "asset \"{}\" corrupted, it can not be decompressed: {}", asset, error)
+        }
+    }
+}
+impl std::error::Error for AssetError {}
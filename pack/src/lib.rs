@@ -0,0 +1,34 @@
+//! Asset pack format shared by the compiler, which encodes it, and the game, which decodes it.
+//!
+//! A pack starts with `MAGIC`, `FORMAT_VERSION` and a table of contents, followed by the records.
+//! Every record starts with its kind byte, its `#` terminated name and its compression,
+//! its fields end with `END`, numbers are big endian except the vertices and indices,
+//! which are little endian and aligned so the GPU can read them straight from a mapped pack.
+
+mod error;      pub use error::*;
+mod reader;     pub use reader::*;
+mod writer;     pub use writer::*;
+mod record;     pub use record::*;
+mod texture;    pub use texture::*;
+mod bc1;        pub use bc1::*;
+mod bc3;        pub use bc3::*;
+mod mesh;       pub use mesh::*;
+mod animation;  pub use animation::*;
+mod material;   pub use material::*;
+mod compression; pub use compression::*;
+mod bytes;      pub use bytes::*;
+mod quantize;   pub use quantize::*;
+mod bounds;     pub use bounds::*;
+mod scene;      pub use scene::*;
+
+// vertices and indices are stored the way little endian GPUs and hosts lay them out in memory
+#[cfg(target_endian = "big")]
+compile_error!("asset packs can only be read and written on little endian hosts");
+
+/// First bytes of every compiled asset pack
+pub const MAGIC: &[u8;4] = b"NXPK";
+/// Bumped every time the layout of the pack or of one of its records changes
+pub const FORMAT_VERSION: u32 = 14;
+/// Local address a running game listens on for the names of the assets `compiler --watch` just rebuilt,
+/// one name per line
+pub const HOT_RELOAD_ADDRESS: &str = "127.0.0.1:47810";
@@ -0,0 +1,80 @@
+use crate::{Reader, Writer, AssetError, VertexType};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    /// fragments with an alpha below the cutoff are discarded
+    Mask(f32),
+    Blend
+}
+
+/// glTF metallic-roughness material, textures are referenced by the name of their texture record
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaterialRecord {
+    pub name: String,
+    /// vertex type of the mesh the material was exported with, tells which shader renders it
+    pub shader: VertexType,
+    pub base_color_factor: [f32;4],
+    pub base_color_texture: Option<String>,
+    pub normal_texture: Option<String>,
+    pub metallic_roughness_texture: Option<String>,
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool
+}
+impl MaterialRecord {
+    pub const KIND: u8 = b'T';
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut res = Writer(Vec::new());
+        res.append_header(Self::KIND, &self.name);
+        res.append_string(self.shader.name());
+        res.append_vec4_f32(self.base_color_factor);
+        for texture in [&self.base_color_texture, &self.normal_texture, &self.metallic_roughness_texture] {
+            res.append_string(texture.as_deref().unwrap_or(""));
+        }
+        match self.alpha_mode {
+            AlphaMode::Opaque => { res.append_u8(0); res.append_f32(0.) },
+            AlphaMode::Mask(cutoff) => { res.append_u8(1); res.append_f32(cutoff) },
+            AlphaMode::Blend => { res.append_u8(2); res.append_f32(0.) }
+        }
+        res.append_u8(self.double_sided as u8);
+        res.append_bytes(b"END");
+        res.0
+    }
+    /// Reads the fields that follow the record header
+    pub fn decode(name: String, reader: &mut Reader) -> Result<Self, AssetError> {
+        let shader = reader.read_string()?;
+        let shader = match VertexType::from_name(&shader) {
+            Some(v) => v,
+            None => return Err(AssetError::UnknownVertexType { vertex_type: shader })
+        };
+        let base_color_factor = reader.read_vec4()?;
+        let mut read_texture = || -> Result<Option<String>, AssetError> {
+            let texture = reader.read_string()?;
+            Ok(if texture.is_empty() { None } else { Some(texture) })
+        };
+        let base_color_texture = read_texture()?;
+        let normal_texture = read_texture()?;
+        let metallic_roughness_texture = read_texture()?;
+        let alpha_mode = reader.read_u8()?;
+        let alpha_cutoff = reader.read_f32()?;
+        let alpha_mode = match alpha_mode {
+            0 => AlphaMode::Opaque,
+            1 => AlphaMode::Mask(alpha_cutoff),
+            2 => AlphaMode::Blend,
+            alpha_mode => return Err(AssetError::UnknownAlphaMode { asset: name, alpha_mode })
+        };
+        let double_sided = reader.read_u8()? != 0;
+        reader.read_end(&name)?;
+        Ok(Self {
+            name,
+            shader,
+            base_color_factor,
+            base_color_texture,
+            normal_texture,
+            metallic_roughness_texture,
+            alpha_mode,
+            double_sided
+        })
+    }
+}
@@ -0,0 +1,448 @@
+use std::ops::Range;
+
+use crate::{Reader, Writer, AssetError, Slice, Bounds, RECORD_ALIGNMENT};
+
+/// Joints ids fit in a u16, `u16::MAX` marks a missing parent
+pub const NO_JOINT: u16 = u16::MAX;
+/// Joints the game can animate in a single armature, the compiler rejects larger skins
+pub const MAX_JOINTS: usize = 128;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VertexBasic {
+    pub position: [f32;3]
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VertexU {
+    pub position: [f32;3],
+    pub uv: [f32;2]
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VertexNU {
+    pub position: [f32;3],
+    pub normal: [f32;3],
+    pub uv: [f32;2]
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VertexNUS {
+    pub position: [f32;3],
+    pub normal: [f32;3],
+    pub uv: [f32;2],
+    /// ids of the joints, below `MAX_JOINTS`, unused influences have a weight of 0
+    pub joints: [u32;4],
+    /// add up to 1, from the largest to the smallest
+    pub weights: [f32;4]
+}
+
+/// `VertexNU` with a MikkTSpace tangent, w is the sign of the bitangent
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VertexNUT {
+    pub position: [f32;3],
+    pub normal: [f32;3],
+    pub uv: [f32;2],
+    pub tangent: [f32;4]
+}
+
+/// `VertexNUS` with a MikkTSpace tangent, w is the sign of the bitangent
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VertexNUST {
+    pub position: [f32;3],
+    pub normal: [f32;3],
+    pub uv: [f32;2],
+    pub tangent: [f32;4],
+    pub joints: [u32;4],
+    pub weights: [f32;4]
+}
+
+/// `VertexNU` with an octahedral snorm16 normal and an unorm16 uv
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VertexNUQ {
+    pub position: [f32;3],
+    pub normal: [i16;2],
+    pub uv: [u16;2]
+}
+
+/// `VertexNUS` with an octahedral snorm16 normal, an unorm16 uv, u8 joints and unorm8 weights
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VertexNUSQ {
+    pub position: [f32;3],
+    pub normal: [i16;2],
+    pub uv: [u16;2],
+    pub joints: [u8;4],
+    pub weights: [u8;4]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VertexType {
+    Basic,
+    U,
+    NU,
+    NUS,
+    /// quantized `NU`
+    NUQ,
+    /// quantized `NUS`
+    NUSQ,
+    /// `NU` with tangents, for normal mapping
+    NUT,
+    /// `NUS` with tangents, for normal mapping
+    NUST
+}
+impl VertexType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Basic => "Basic",
+            Self::U => "U",
+            Self::NU => "NU",
+            Self::NUS => "NUS",
+            Self::NUQ => "NUQ",
+            Self::NUSQ => "NUSQ",
+            Self::NUT => "NUT",
+            Self::NUST => "NUST"
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Basic" => Some(Self::Basic),
+            "U" => Some(Self::U),
+            "NU" => Some(Self::NU),
+            "NUS" => Some(Self::NUS),
+            "NUQ" => Some(Self::NUQ),
+            "NUSQ" => Some(Self::NUSQ),
+            "NUT" => Some(Self::NUT),
+            "NUST" => Some(Self::NUST),
+            _ => None
+        }
+    }
+    pub fn is_quantized(&self) -> bool {
+        matches!(self, Self::NUQ | Self::NUSQ)
+    }
+    /// The type holding the same attributes at full precision
+    pub fn unquantized(&self) -> Self {
+        match self {
+            Self::NUQ => Self::NU,
+            Self::NUSQ => Self::NUS,
+            v => *v
+        }
+    }
+    /// Skinned vertices, their meshes come with joints
+    pub fn has_joints(&self) -> bool {
+        matches!(self, Self::NUS | Self::NUSQ | Self::NUST)
+    }
+    pub fn has_tangents(&self) -> bool {
+        matches!(self, Self::NUT | Self::NUST)
+    }
+}
+
+/// Stored little endian in the GPU layout, read without decoding
+#[derive(Debug, Clone, PartialEq)]
+pub enum Vertices {
+    Basic(Slice<VertexBasic>),
+    U(Slice<VertexU>),
+    NU(Slice<VertexNU>),
+    NUS(Slice<VertexNUS>),
+    NUQ(Slice<VertexNUQ>),
+    NUSQ(Slice<VertexNUSQ>),
+    NUT(Slice<VertexNUT>),
+    NUST(Slice<VertexNUST>)
+}
+impl Vertices {
+    pub fn vertex_type(&self) -> VertexType {
+        match self {
+            Self::Basic(_) => VertexType::Basic,
+            Self::U(_) => VertexType::U,
+            Self::NU(_) => VertexType::NU,
+            Self::NUS(_) => VertexType::NUS,
+            Self::NUQ(_) => VertexType::NUQ,
+            Self::NUSQ(_) => VertexType::NUSQ,
+            Self::NUT(_) => VertexType::NUT,
+            Self::NUST(_) => VertexType::NUST
+        }
+    }
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Basic(v) => v.len(),
+            Self::U(v) => v.len(),
+            Self::NU(v) => v.len(),
+            Self::NUS(v) => v.len(),
+            Self::NUQ(v) => v.len(),
+            Self::NUSQ(v) => v.len(),
+            Self::NUT(v) => v.len(),
+            Self::NUST(v) => v.len()
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Vertices in the layout the GPU expects
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            Self::Basic(v) => v.bytes(),
+            Self::U(v) => v.bytes(),
+            Self::NU(v) => v.bytes(),
+            Self::NUS(v) => v.bytes(),
+            Self::NUQ(v) => v.bytes(),
+            Self::NUSQ(v) => v.bytes(),
+            Self::NUT(v) => v.bytes(),
+            Self::NUST(v) => v.bytes()
+        }
+    }
+}
+
+/// Triangle list indices, u16 whenever every vertex can be addressed with it
+#[derive(Debug, Clone, PartialEq)]
+pub enum Indices {
+    U16(Slice<u16>),
+    U32(Slice<u32>)
+}
+impl Indices {
+    /// Picks the smallest index size able to address `vertices_len` vertices
+    pub fn new(indices: Vec<u32>, vertices_len: usize) -> Self {
+        if vertices_len <= u16::MAX as usize + 1 {
+            Self::U16(indices.into_iter().map(|i| i as u16).collect())
+        } else {
+            Self::U32(indices.into())
+        }
+    }
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U16(v) => v.len(),
+            Self::U32(v) => v.len()
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Size in bytes of a single index
+    pub fn index_size(&self) -> u8 {
+        match self {
+            Self::U16(_) => 2,
+            Self::U32(_) => 4
+        }
+    }
+    /// Indices in the layout the GPU expects
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            Self::U16(v) => v.bytes(),
+            Self::U32(v) => v.bytes()
+        }
+    }
+}
+
+/// Range of the index buffer drawn with a single material, one per glTF primitive
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubMeshRecord {
+    /// name of the material record, empty for the default material
+    pub material: String,
+    pub indices_start: u32,
+    pub indices_len: u32,
+    pub bounds: Bounds
+}
+
+/// Simplified version of a mesh, its indices follow the ones of the full mesh in the same buffer
+#[derive(Debug, Clone, PartialEq)]
+pub struct LodRecord {
+    /// distance from the camera the level is drawn from
+    pub distance: f32,
+    /// indices of every sub-mesh, in the order of `MeshRecord::sub_meshes`
+    pub indices: Vec<Range<u32>>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JointRecord {
+    pub name: String,
+    /// nearest parent, `NO_JOINT` for the root
+    pub parent: u16,
+    /// from farthest to nearest
+    pub parents: Vec<u16>,
+    pub tpose: [[f32;4];4],
+    pub tpose_local: [[f32;4];4],
+    pub ibm: [[f32;4];4],
+    /// vertices the joint moves, in its own space through `ibm`, `Bounds::EMPTY` when it moves none
+    pub bounds: Bounds
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshRecord {
+    pub name: String,
+    pub vertices: Vertices,
+    pub indices: Indices,
+    pub sub_meshes: Vec<SubMeshRecord>,
+    /// of the full mesh, at rest for skinned meshes
+    pub bounds: Bounds,
+    /// simplified levels, from the closest to the farthest
+    pub lods: Vec<LodRecord>,
+    /// only written for skinned (`NUS`, `NUSQ` and `NUST`) meshes
+    pub joints: Vec<JointRecord>
+}
+impl MeshRecord {
+    pub const KIND: u8 = b'M';
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut res = Writer(Vec::with_capacity(self.vertices.bytes().len() + 64));
+        res.append_header(Self::KIND, &self.name);
+        res.append_string(self.vertices.vertex_type().name());
+        res.append_u32(self.vertices.len() as u32);
+        res.align(RECORD_ALIGNMENT);
+        match &self.vertices {
+            Vertices::Basic(vertices) => res.append_le(vertices),
+            Vertices::U(vertices) => res.append_le(vertices),
+            Vertices::NU(vertices) => res.append_le(vertices),
+            Vertices::NUS(vertices) => res.append_le(vertices),
+            Vertices::NUQ(vertices) => res.append_le(vertices),
+            Vertices::NUSQ(vertices) => res.append_le(vertices),
+            Vertices::NUT(vertices) => res.append_le(vertices),
+            Vertices::NUST(vertices) => res.append_le(vertices)
+        }
+        res.append_u8(self.indices.index_size());
+        res.append_u32(self.indices.len() as u32);
+        res.align(RECORD_ALIGNMENT);
+        match &self.indices {
+            Indices::U16(indices) => res.append_le(indices),
+            Indices::U32(indices) => res.append_le(indices)
+        }
+        res.append_u32(self.sub_meshes.len() as u32);
+        for sub_mesh in self.sub_meshes.iter() {
+            res.append_string(&sub_mesh.material);
+            res.append_u32(sub_mesh.indices_start);
+            res.append_u32(sub_mesh.indices_len);
+            res.append_bounds(sub_mesh.bounds);
+        }
+        res.append_bounds(self.bounds);
+        res.append_u8(self.lods.len() as u8);
+        for lod in self.lods.iter() {
+            res.append_f32(lod.distance);
+            for indices in lod.indices.iter() {
+                res.append_u32(indices.start);
+                res.append_u32(indices.end - indices.start);
+            }
+        }
+        if self.vertices.vertex_type().has_joints() {
+            res.append_u16(self.joints.len() as u16);
+            for joint in self.joints.iter() {
+                res.append_string(&joint.name);
+                res.append_u16(joint.parent);
+                for parent in joint.parents.iter() {
+                    res.append_u16(*parent);
+                }
+                res.append_u16(NO_JOINT);
+                res.append_mat4x4(joint.tpose);
+                res.append_mat4x4(joint.tpose_local);
+                res.append_mat4x4(joint.ibm);
+                res.append_bounds(joint.bounds);
+            }
+        }
+        res.append_bytes(b"END");
+        res.0
+    }
+    /// Reads the fields that follow the record header
+    pub fn decode(name: String, reader: &mut Reader) -> Result<Self, AssetError> {
+        let vertex_type = reader.read_string()?;
+        let vertex_type = match VertexType::from_name(&vertex_type) {
+            Some(v) => v,
+            None => return Err(AssetError::UnknownVertexType { vertex_type })
+        };
+        let len = reader.read_u32()? as usize;
+        reader.align(RECORD_ALIGNMENT)?;
+        let vertices = match vertex_type {
+            VertexType::Basic => Vertices::Basic(read_slice(reader, len)?),
+            VertexType::U => Vertices::U(read_slice(reader, len)?),
+            VertexType::NU => Vertices::NU(read_slice(reader, len)?),
+            VertexType::NUS => Vertices::NUS(read_slice(reader, len)?),
+            VertexType::NUQ => Vertices::NUQ(read_slice(reader, len)?),
+            VertexType::NUSQ => Vertices::NUSQ(read_slice(reader, len)?),
+            VertexType::NUT => Vertices::NUT(read_slice(reader, len)?),
+            VertexType::NUST => Vertices::NUST(read_slice(reader, len)?)
+        };
+        let index_size = reader.read_u8()?;
+        let indices_len = reader.read_u32()? as usize;
+        reader.align(RECORD_ALIGNMENT)?;
+        let indices = match index_size {
+            2 => Indices::U16(read_slice(reader, indices_len)?),
+            4 => Indices::U32(read_slice(reader, indices_len)?),
+            _ => return Err(AssetError::UnknownIndexSize { index_size })
+        };
+        let sub_meshes_len = reader.read_u32()? as usize;
+        let sub_meshes = read_list(reader, sub_meshes_len, |reader| Ok(SubMeshRecord {
+            material: reader.read_string()?,
+            indices_start: reader.read_u32()?,
+            indices_len: reader.read_u32()?,
+            bounds: reader.read_bounds()?
+        }))?;
+        if sub_meshes.iter().any(|sub_mesh| sub_mesh.indices_start as usize + sub_mesh.indices_len as usize > indices.len()) {
+            return Err(AssetError::SubMeshOutOfRange { asset: name })
+        }
+        let bounds = reader.read_bounds()?;
+        let lods_len = reader.read_u8()?;
+        let mut lods = Vec::with_capacity(lods_len as usize);
+        for _ in 0..lods_len {
+            let distance = reader.read_f32()?;
+            let mut lod_indices = Vec::with_capacity(sub_meshes.len());
+            for _ in 0..sub_meshes.len() {
+                let start = reader.read_u32()?;
+                let end = match start.checked_add(reader.read_u32()?) {
+                    Some(v) if v as usize <= indices.len() => v,
+                    _ => return Err(AssetError::SubMeshOutOfRange { asset: name })
+                };
+                lod_indices.push(start..end);
+            }
+            lods.push(LodRecord { distance, indices: lod_indices });
+        }
+        let mut joints = Vec::new();
+        if vertex_type.has_joints() {
+            let joints_length = reader.read_u16()?;
+            for _ in 0..joints_length {
+                joints.push(JointRecord {
+                    name: reader.read_string()?,
+                    parent: reader.read_u16()?,
+                    parents: {
+                        let mut parents = Vec::new();
+                        let mut parent = reader.read_u16()?;
+                        while parent != NO_JOINT {
+                            parents.push(parent);
+                            parent = reader.read_u16()?;
+                        }
+                        parents
+                    },
+                    tpose: reader.read_mat4x4()?,
+                    tpose_local: reader.read_mat4x4()?,
+                    ibm: reader.read_mat4x4()?,
+                    bounds: reader.read_bounds()?
+                });
+            }
+        }
+        reader.read_end(&name)?;
+        Ok(Self { name, vertices, indices, sub_meshes, bounds, lods, joints })
+    }
+}
+
+/// GPU data, shared with the reader when it is aligned
+fn read_slice<T: bytemuck::Pod>(reader: &mut Reader, len: usize) -> Result<Slice<T>, AssetError> {
+    let size = match len.checked_mul(std::mem::size_of::<T>()) {
+        Some(v) => v,
+        None => return Err(AssetError::UnexpectedEof { offset: reader.position() })
+    };
+    Ok(Slice::from_le_bytes(reader.read_shared(size)?))
+}
+
+#[inline]
+fn read_list<V>(
+    reader: &mut Reader,
+    len: usize,
+    f: fn(&mut Reader) -> Result<V, AssetError>
+) -> Result<Vec<V>, AssetError> {
+    let mut res = Vec::new();
+    for _ in 0..len {
+        res.push(f(reader)?);
+    }
+    Ok(res)
+}
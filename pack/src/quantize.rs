@@ -0,0 +1,72 @@
+//! Compact vertex attributes: octahedral snorm16 normals, unorm16 uvs, u8 joints and unorm8 weights
+
+use crate::{Vertices, VertexNU, VertexNUS, VertexNUQ, VertexNUSQ};
+
+/// Unit normal folded onto an octahedron, two snorm16 components
+pub fn encode_octahedral(n: [f32;3]) -> [i16;2] {
+    let l1 = n[0].abs() + n[1].abs() + n[2].abs();
+    if l1 == 0. { return [0, 0] }
+    let (mut x, mut y) = (n[0] / l1, n[1] / l1);
+    if n[2] < 0. {
+        let sign = |v: f32| if v >= 0. { 1. } else { -1. };
+        (x, y) = ((1. - y.abs()) * sign(x), (1. - x.abs()) * sign(y));
+    }
+    [x, y].map(|v| (v.clamp(-1., 1.) * i16::MAX as f32).round() as i16)
+}
+/// Inverse of `encode_octahedral`, the shaders do the same
+pub fn decode_octahedral(e: [i16;2]) -> [f32;3] {
+    let [x, y] = e.map(|v| (v as f32 / i16::MAX as f32).max(-1.));
+    let z = 1. - x.abs() - y.abs();
+    let t = (-z).max(0.);
+    let n = [x + if x >= 0. { -t } else { t }, y + if y >= 0. { -t } else { t }, z];
+    let len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    n.map(|v| v / len)
+}
+
+/// uvs outside of 0 to 1 can not be stored as unorm16
+fn quantize_uv(uv: [f32;2]) -> Option<[u16;2]> {
+    if uv.iter().any(|v| !(0. ..=1.).contains(v)) { return None }
+    Some(uv.map(|v| (v * u16::MAX as f32).round() as u16))
+}
+
+/// Weights rounded to unorm8, the rounding error goes to the largest one so they still add up to 1
+fn quantize_weights(weights: [f32;4]) -> [u8;4] {
+    let sum: f32 = weights.iter().sum();
+    let weights = weights.map(|w| if sum > 0. { w / sum } else { 0. });
+    let mut res = weights.map(|w| (w * 255.).round() as i32);
+    let largest = (0..4).fold(0, |best, i| if weights[i] > weights[best] { i } else { best });
+    res[largest] += 255 - res.iter().sum::<i32>();
+    res.map(|w| w.clamp(0, 255) as u8)
+}
+
+impl VertexNUQ {
+    pub fn quantize(v: &VertexNU) -> Option<Self> {
+        Some(Self { position: v.position, normal: encode_octahedral(v.normal), uv: quantize_uv(v.uv)? })
+    }
+}
+impl VertexNUSQ {
+    pub fn quantize(v: &VertexNUS) -> Option<Self> {
+        // the joint of an unused influence does not matter, it is stored as 0
+        let joints = [0, 1, 2, 3].map(|i| if v.weights[i] > 0. { v.joints[i] } else { 0 });
+        if joints.iter().any(|joint| *joint > u8::MAX as u32) { return None }
+        Some(Self {
+            position: v.position,
+            normal: encode_octahedral(v.normal),
+            uv: quantize_uv(v.uv)?,
+            joints: joints.map(|joint| joint as u8),
+            weights: quantize_weights(v.weights)
+        })
+    }
+}
+
+impl Vertices {
+    /// Compact version of `NU` and `NUS` vertices, None for the other types
+    /// or when a vertex has a uv outside of 0 to 1 or a joint past 255
+    pub fn quantize(&self) -> Option<Self> {
+        match self {
+            Self::NU(vertices) => Some(Self::NUQ(vertices.iter().map(VertexNUQ::quantize).collect::<Option<Vec<_>>>()?.into())),
+            Self::NUS(vertices) => Some(Self::NUSQ(vertices.iter().map(VertexNUSQ::quantize).collect::<Option<Vec<_>>>()?.into())),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::path::Path;
+
+use crate::{AssetError, Bytes, MAGIC, FORMAT_VERSION};
+
+/// Table of contents entry, describes where a record is inside the pack
+#[derive(Debug, Clone, PartialEq)]
+pub struct TocEntry {
+    pub kind: u8,
+    pub name: String,
+    pub offset: usize,
+    pub length: usize
+}
+
+/// Cloning a reader shares the pack, records can be decoded in parallel by readers of their own
+#[derive(Clone)]
+pub struct Reader(Bytes, usize);
+impl Reader {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, AssetError> {
+        match std::fs::read(path.as_ref()) {
+            Ok(v) => Ok(Self(v.into(), 0)),
+            Err(error) => Err(AssetError::Io { path: path.as_ref().to_path_buf(), error })
+        }
+    }
+    /// Memory maps the pack, the vertices and indices of its records are then uploaded straight from the file.
+    ///
+    /// # Safety
+    /// The file must not be written while the reader or a record read from it exists
+    pub unsafe fn map(path: impl AsRef<Path>) -> Result<Self, AssetError> {
+        match Bytes::map(path.as_ref()) {
+            Ok(v) => Ok(Self(v, 0)),
+            Err(error) => Err(AssetError::Io { path: path.as_ref().to_path_buf(), error })
+        }
+    }
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self(bytes.into(), 0)
+    }
+    /// Checks the magic number and the format version, then reads the table of contents
+    pub fn read_toc(&mut self, path: impl AsRef<Path>) -> Result<Vec<TocEntry>, AssetError> {
+        let path = path.as_ref();
+        if self.0.len() < MAGIC.len() + 8 || &self.0[..MAGIC.len()] != MAGIC {
+            return Err(AssetError::NotAPack { path: path.to_path_buf() })
+        }
+        self.1 = MAGIC.len();
+        let version = self.read_u32()?;
+        if version != FORMAT_VERSION {
+            return Err(AssetError::UnsupportedVersion { path: path.to_path_buf(), version })
+        }
+        let entries = self.read_u32()? as usize;
+        let mut toc = Vec::new();
+        for _ in 0..entries {
+            toc.push(TocEntry {
+                kind: self.read_u8()?,
+                name: self.read_string()?,
+                offset: self.read_u32()? as usize,
+                length: self.read_u32()? as usize
+            });
+        }
+        Ok(toc)
+    }
+    /// The whole pack
+    #[inline]
+    pub fn bytes(&self) -> &[u8] {
+        &self.0
+    }
+    #[inline]
+    pub fn seek(&mut self, offset: usize) {
+        self.1 = offset
+    }
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.1
+    }
+    /// Advances `len` bytes, failing if the file ends before that
+    #[inline]
+    pub fn read_bytes(&mut self, len: usize) -> Result<&[u8], AssetError> {
+        let start = self.1;
+        match start.checked_add(len).and_then(|end| self.0.get(start..end)) {
+            Some(v) => {
+                self.1 += len;
+                Ok(v)
+            }
+            None => Err(AssetError::UnexpectedEof { offset: self.0.len().min(start) })
+        }
+    }
+    /// Advances `len` bytes and shares them instead of copying them
+    pub fn read_shared(&mut self, len: usize) -> Result<Bytes, AssetError> {
+        let start = self.1;
+        self.read_bytes(len)?;
+        Ok(self.0.slice(start..start + len))
+    }
+    /// Skips the padding the writer added to align the next field to `alignment` bytes
+    #[inline]
+    pub fn align(&mut self, alignment: usize) -> Result<(), AssetError> {
+        self.read_bytes(self.1.next_multiple_of(alignment) - self.1)?;
+        Ok(())
+    }
+    #[inline]
+    pub fn read_string(&mut self) -> Result<String, AssetError> {
+        let start = self.1;
+        let len = match self.0.get(start..).and_then(|v| v.iter().position(|b| *b == b'#')) {
+            Some(v) => v,
+            None => return Err(AssetError::UnexpectedEof { offset: self.0.len() })
+        };
+        let res = match std::str::from_utf8(&self.0[start..start + len]) {
+            Ok(v) => v.to_string(),
+            Err(_) => return Err(AssetError::InvalidName { offset: start })
+        };
+        self.1 += len + 1;
+        Ok(res)
+    }
+    #[inline]
+    pub fn read_end(&mut self, name: &str) -> Result<(), AssetError> {
+        let offset = self.1;
+        if self.read_bytes(3)? != b"END" {
+            return Err(AssetError::BadEndMarker { asset: name.to_string(), offset })
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn read_u8(&mut self) -> Result<u8, AssetError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+    #[inline]
+    pub fn read_u16(&mut self) -> Result<u16, AssetError> {
+        let v = self.read_bytes(2)?;
+        Ok(u16::from_be_bytes([ v[0], v[1] ]))
+    }
+    #[inline]
+    pub fn read_u32(&mut self) -> Result<u32, AssetError> {
+        let v = self.read_bytes(4)?;
+        Ok(u32::from_be_bytes([ v[0], v[1], v[2], v[3] ]))
+    }
+    #[inline]
+    fn read_f32s<const N: usize>(&mut self) -> Result<[f32;N], AssetError> {
+        let v = self.read_bytes(N * 4)?;
+        let mut res = [0.;N];
+        for (i, bytes) in v.chunks_exact(4).enumerate() {
+            res[i] = f32::from_be_bytes([ bytes[0], bytes[1], bytes[2], bytes[3] ]);
+        }
+        Ok(res)
+    }
+
+    #[inline]
+    pub fn read_f32(&mut self) -> Result<f32, AssetError> {
+        Ok(self.read_f32s::<1>()?[0])
+    }
+    #[inline]
+    pub fn read_vec3(&mut self) -> Result<[f32;3], AssetError> {
+        self.read_f32s()
+    }
+    #[inline]
+    pub fn read_vec2(&mut self) -> Result<[f32;2], AssetError> {
+        self.read_f32s()
+    }
+    #[inline]
+    pub fn read_vec4(&mut self) -> Result<[f32;4], AssetError> {
+        self.read_f32s()
+    }
+    #[inline]
+    pub fn read_mat4x4(&mut self) -> Result<[[f32;4];4], AssetError> {
+        let v = self.read_f32s::<16>()?;
+        Ok([
+            [ v[0], v[1], v[2], v[3] ],
+            [ v[4], v[5], v[6], v[7] ],
+            [ v[8], v[9], v[10], v[11] ],
+            [ v[12], v[13], v[14], v[15] ]
+        ])
+    }
+}
@@ -0,0 +1,70 @@
+use crate::{Reader, AssetError, TocEntry, TextureRecord, MeshRecord, AnimationRecord, MaterialRecord, SceneRecord};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Record {
+    Texture(TextureRecord),
+    Mesh(MeshRecord),
+    Animation(AnimationRecord),
+    Material(MaterialRecord),
+    Scene(SceneRecord)
+}
+impl Record {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Texture(v) => &v.name,
+            Self::Mesh(v) => &v.name,
+            Self::Animation(v) => &v.name,
+            Self::Material(v) => &v.name,
+            Self::Scene(v) => &v.name
+        }
+    }
+    pub fn kind(&self) -> u8 {
+        match self {
+            Self::Texture(_) => TextureRecord::KIND,
+            Self::Mesh(_) => MeshRecord::KIND,
+            Self::Animation(_) => AnimationRecord::KIND,
+            Self::Material(_) => MaterialRecord::KIND,
+            Self::Scene(_) => SceneRecord::KIND
+        }
+    }
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Texture(v) => v.encode(),
+            Self::Mesh(v) => v.encode(),
+            Self::Animation(v) => v.encode(),
+            Self::Material(v) => v.encode(),
+            Self::Scene(v) => v.encode()
+        }
+    }
+    /// Reads a whole record, kind byte included
+    pub fn decode(reader: &mut Reader) -> Result<Self, AssetError> {
+        let offset = reader.position();
+        let kind = reader.read_u8()?;
+        if ![TextureRecord::KIND, MeshRecord::KIND, AnimationRecord::KIND, MaterialRecord::KIND, SceneRecord::KIND].contains(&kind) {
+            return Err(AssetError::UnknownAssetType { offset, kind })
+        }
+        let name = reader.read_string()?;
+        reader.read_fields(name, |name, reader| match kind {
+            TextureRecord::KIND => Ok(Self::Texture(TextureRecord::decode(name, reader)?)),
+            MeshRecord::KIND => Ok(Self::Mesh(MeshRecord::decode(name, reader)?)),
+            AnimationRecord::KIND => Ok(Self::Animation(AnimationRecord::decode(name, reader)?)),
+            SceneRecord::KIND => Ok(Self::Scene(SceneRecord::decode(name, reader)?)),
+            _ => Ok(Self::Material(MaterialRecord::decode(name, reader)?))
+        })
+    }
+}
+
+impl Reader {
+    /// Reads the record a table of contents entry points to, checking it matches the entry
+    pub fn read_record(&mut self, entry: &TocEntry) -> Result<Record, AssetError> {
+        self.seek(entry.offset);
+        let record = Record::decode(self)?;
+        if record.kind() != entry.kind {
+            return Err(AssetError::KindMismatch { asset: entry.name.clone(), listed: entry.kind, found: record.kind() })
+        }
+        if self.position() != entry.offset + entry.length {
+            return Err(AssetError::LengthMismatch { asset: entry.name.clone() })
+        }
+        Ok(record)
+    }
+}
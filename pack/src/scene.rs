@@ -0,0 +1,103 @@
+use crate::{Reader, Writer, AssetError};
+
+/// Node of a scene, its transform is relative to its parent
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneNode {
+    pub name: String,
+    pub translation: [f32;3],
+    /// quaternion, x, y, z and w
+    pub rotation: [f32;4],
+    pub scale: [f32;3],
+    /// name of the mesh record drawn at the node
+    pub mesh: Option<String>,
+    /// custom properties of the node, the JSON of its glTF extras, empty when it has none
+    pub extras: String,
+    /// ids in `SceneRecord::nodes`, always after the id of the node
+    pub children: Vec<u16>
+}
+
+/// Node hierarchy of a glTF scene, spawned as a whole by the game
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneRecord {
+    pub name: String,
+    /// every parent comes before its children
+    pub nodes: Vec<SceneNode>,
+    /// ids of the nodes without a parent
+    pub roots: Vec<u16>
+}
+impl SceneRecord {
+    pub const KIND: u8 = b'S';
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut res = Writer(Vec::new());
+        res.append_header(Self::KIND, &self.name);
+        res.append_u16(self.nodes.len() as u16);
+        for node in self.nodes.iter() {
+            res.append_string(&node.name);
+            res.append_vec3_f32(node.translation);
+            res.append_vec4_f32(node.rotation);
+            res.append_vec3_f32(node.scale);
+            res.append_string(node.mesh.as_deref().unwrap_or(""));
+            // JSON can hold a #, so the extras are stored with their length
+            res.append_u32(node.extras.len() as u32);
+            res.append_bytes(node.extras.as_bytes());
+            res.append_u16(node.children.len() as u16);
+            for child in node.children.iter() {
+                res.append_u16(*child);
+            }
+        }
+        res.append_u16(self.roots.len() as u16);
+        for root in self.roots.iter() {
+            res.append_u16(*root);
+        }
+        res.append_bytes(b"END");
+        res.0
+    }
+    /// Reads the fields that follow the record header
+    pub fn decode(name: String, reader: &mut Reader) -> Result<Self, AssetError> {
+        let nodes_len = reader.read_u16()?;
+        let mut nodes = Vec::with_capacity(nodes_len as usize);
+        for id in 0..nodes_len {
+            let node_name = reader.read_string()?;
+            let translation = reader.read_vec3()?;
+            let rotation = reader.read_vec4()?;
+            let scale = reader.read_vec3()?;
+            let mesh = reader.read_string()?;
+            let extras_len = reader.read_u32()? as usize;
+            let offset = reader.position();
+            let extras = match std::str::from_utf8(reader.read_bytes(extras_len)?) {
+                Ok(v) => v.to_string(),
+                Err(_) => return Err(AssetError::InvalidName { offset })
+            };
+            let children_len = reader.read_u16()?;
+            let mut children = Vec::with_capacity(children_len as usize);
+            for _ in 0..children_len {
+                let child = reader.read_u16()?;
+                if child <= id || child >= nodes_len {
+                    return Err(AssetError::NodeOutOfRange { asset: name })
+                }
+                children.push(child);
+            }
+            nodes.push(SceneNode {
+                name: node_name,
+                translation,
+                rotation,
+                scale,
+                mesh: if mesh.is_empty() { None } else { Some(mesh) },
+                extras,
+                children
+            });
+        }
+        let roots_len = reader.read_u16()?;
+        let mut roots = Vec::with_capacity(roots_len as usize);
+        for _ in 0..roots_len {
+            let root = reader.read_u16()?;
+            if root >= nodes_len {
+                return Err(AssetError::NodeOutOfRange { asset: name })
+            }
+            roots.push(root);
+        }
+        reader.read_end(&name)?;
+        Ok(Self { name, nodes, roots })
+    }
+}
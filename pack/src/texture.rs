@@ -0,0 +1,121 @@
+use crate::{Reader, Writer, AssetError};
+
+/// Layout of the texels of every mip level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFormat {
+    /// one byte per channel, pixels row by row, 3 channel textures are stored with an opaque alpha
+    Raw,
+    /// 8 bytes per 4x4 block, blocks row by row, no alpha
+    Bc1,
+    /// 16 bytes per 4x4 block, a BC1 block after an alpha block, for textures with alpha
+    Bc3
+}
+impl TextureFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Raw => "RAW",
+            Self::Bc1 => "BC1",
+            Self::Bc3 => "BC3"
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "RAW" => Some(Self::Raw),
+            "BC1" => Some(Self::Bc1),
+            "BC3" => Some(Self::Bc3),
+            _ => None
+        }
+    }
+    /// Bytes taken by an image of this size
+    pub fn level_len(&self, width: u32, height: u32, channels: u8) -> usize {
+        match self {
+            Self::Raw => width as usize * height as usize * stored_channels(channels),
+            Self::Bc1 => width.div_ceil(4) as usize * height.div_ceil(4) as usize * 8,
+            Self::Bc3 => width.div_ceil(4) as usize * height.div_ceil(4) as usize * 16
+        }
+    }
+}
+
+/// GPUs have no 3 channel formats
+#[inline]
+pub fn stored_channels(channels: u8) -> usize {
+    if channels == 3 { 4 } else { channels as usize }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// colors, converted to linear by the GPU when sampled
+    Srgb,
+    /// data such as normals, roughness or masks, sampled as stored
+    Linear
+}
+
+/// Number of levels of a full mip chain, down to 1x1
+pub fn mip_levels(width: u32, height: u32) -> u8 {
+    (32 - width.max(height).max(1).leading_zeros()) as u8
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureRecord {
+    pub name: String,
+    pub format: TextureFormat,
+    /// 1 to 4, sRGB textures always have 3 or 4
+    pub channels: u8,
+    pub color_space: ColorSpace,
+    pub width: u32,
+    pub height: u32,
+    /// from the full size image down, every level is half the size of the previous one
+    pub mips: Vec<Vec<u8>>
+}
+impl TextureRecord {
+    pub const KIND: u8 = b'I';
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut res = Writer(Vec::with_capacity(self.mips.iter().map(Vec::len).sum::<usize>() + self.name.len() + 32));
+        res.append_header(Self::KIND, &self.name);
+        res.append_string(self.format.name());
+        res.append_u8(self.channels);
+        res.append_u8(match self.color_space {
+            ColorSpace::Srgb => 0,
+            ColorSpace::Linear => 1
+        });
+        res.append_u32(self.width);
+        res.append_u32(self.height);
+        res.append_u8(self.mips.len() as u8);
+        for level in self.mips.iter() {
+            res.append_bytes(level);
+        }
+        res.append_bytes(b"END");
+        res.0
+    }
+    /// Reads the fields that follow the record header
+    pub fn decode(name: String, reader: &mut Reader) -> Result<Self, AssetError> {
+        let format = reader.read_string()?;
+        let format = match TextureFormat::from_name(&format) {
+            Some(v) => v,
+            None => return Err(AssetError::UnknownTextureFormat { format })
+        };
+        let channels = reader.read_u8()?;
+        let color_space = match reader.read_u8()? {
+            0 => ColorSpace::Srgb,
+            1 => ColorSpace::Linear,
+            color_space => return Err(AssetError::UnknownColorSpace { asset: name, color_space })
+        };
+        if !(1..=4).contains(&channels) || (color_space == ColorSpace::Srgb && channels < 3) {
+            return Err(AssetError::InvalidChannels { asset: name, channels })
+        }
+        let width = reader.read_u32()?;
+        let height = reader.read_u32()?;
+        let levels = reader.read_u8()?;
+        if levels == 0 || levels > mip_levels(width, height) {
+            return Err(AssetError::InvalidMipLevels { asset: name, levels })
+        }
+        let mut mips = Vec::with_capacity(levels as usize);
+        for level in 0..levels as u32 {
+            let len = format.level_len((width >> level).max(1), (height >> level).max(1), channels);
+            mips.push(reader.read_bytes(len)?.to_vec());
+        }
+        reader.read_end(&name)?;
+        Ok(Self { name, format, channels, color_space, width, height, mips })
+    }
+}
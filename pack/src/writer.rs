@@ -0,0 +1,95 @@
+use crate::{MAGIC, FORMAT_VERSION};
+
+/// Records start at a multiple of it, the GPU data they hold is aligned the same way inside them
+pub const RECORD_ALIGNMENT: usize = 4;
+
+pub struct Writer(pub Vec<u8>);
+impl Writer {
+    /// Builds a pack from a list of records, each one starting with its kind byte and its name.
+    ///
+    /// Layout: magic, format version, records count, table of contents
+    /// (kind, name, offset, length for every record) and then the records, each one aligned to `RECORD_ALIGNMENT`.
+    pub fn pack(records: &[Vec<u8>]) -> Vec<u8> {
+        let entries: Vec<(u8, String)> = records.iter().map(|record| {
+            let name_end = record.iter().position(|b| *b == b'#').expect("record without name");
+            (record[0], String::from_utf8_lossy(&record[1..name_end]).to_string())
+        }).collect();
+        let toc_len: usize = entries.iter().map(|(_, name)| 1 + name.len() + 1 + 4 + 4).sum();
+        let mut offset = (MAGIC.len() + 4 + 4 + toc_len).next_multiple_of(RECORD_ALIGNMENT);
+
+        let mut res = Writer(Vec::with_capacity(offset + records.iter().map(|record| record.len() + RECORD_ALIGNMENT).sum::<usize>()));
+        res.append_bytes(MAGIC);
+        res.append_u32(FORMAT_VERSION);
+        res.append_u32(records.len() as u32);
+        for ((kind, name), record) in entries.into_iter().zip(records.iter()) {
+            res.0.push(kind);
+            res.append_string(name);
+            res.append_u32(offset as u32);
+            res.append_u32(record.len() as u32);
+            offset = (offset + record.len()).next_multiple_of(RECORD_ALIGNMENT);
+        }
+        for record in records {
+            res.align(RECORD_ALIGNMENT);
+            res.append_bytes(record);
+        }
+        res.0
+    }
+    /// Pads with zeros up to a multiple of `alignment` bytes
+    #[inline]
+    pub fn align(&mut self, alignment: usize) {
+        self.0.resize(self.0.len().next_multiple_of(alignment), 0);
+    }
+    /// GPU data as it is laid out in memory, see `Slice::from_le_bytes`
+    #[inline]
+    pub fn append_le<T: bytemuck::Pod>(&mut self, v: &[T]) {
+        self.0.extend_from_slice(bytemuck::cast_slice(v));
+    }
+    /// Names can not contain `#`, it terminates them
+    #[inline]
+    pub fn append_string(&mut self, v: impl AsRef<str>) {
+        self.0.extend(v.as_ref().bytes().filter(|b| *b != b'#'));
+        self.0.push(b'#');
+    }
+    #[inline]
+    pub fn append_u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+    #[inline]
+    pub fn append_bytes(&mut self, v: &[u8]) {
+        self.0.extend_from_slice(v);
+    }
+    #[inline]
+    pub fn append_u16(&mut self, v: u16) {
+        let v = v.to_be_bytes();
+        self.0.push(v[0]); self.0.push(v[1]);
+    }
+    #[inline]
+    pub fn append_u32(&mut self, v: u32) {
+        let v = v.to_be_bytes();
+        self.0.push(v[0]); self.0.push(v[1]); self.0.push(v[2]); self.0.push(v[3]);
+    }
+    #[inline]
+    pub fn append_f32(&mut self, v: f32) {
+        let v = v.to_be_bytes();
+        self.0.push(v[0]); self.0.push(v[1]); self.0.push(v[2]); self.0.push(v[3]);
+    }
+    #[inline]
+    pub fn append_vec3_f32(&mut self, v: [f32;3]) {
+        self.append_f32(v[0]); self.append_f32(v[1]); self.append_f32(v[2])
+    }
+    #[inline]
+    pub fn append_vec2_f32(&mut self, v: [f32;2]) {
+        self.append_f32(v[0]); self.append_f32(v[1])
+    }
+    #[inline]
+    pub fn append_vec4_f32(&mut self, v: [f32;4]) {
+        self.append_f32(v[0]); self.append_f32(v[1]); self.append_f32(v[2]); self.append_f32(v[3])
+    }
+    #[inline]
+    pub fn append_mat4x4(&mut self, v: [[f32;4];4]) {
+        self.append_vec4_f32(v[0]);
+        self.append_vec4_f32(v[1]);
+        self.append_vec4_f32(v[2]);
+        self.append_vec4_f32(v[3]);
+    }
+}
\ No newline at end of file
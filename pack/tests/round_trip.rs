@@ -0,0 +1,416 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use pack::*;
+
+fn texture() -> TextureRecord {
+    TextureRecord {
+        name: "grass".to_string(),
+        format: TextureFormat::Raw,
+        channels: 4,
+        color_space: ColorSpace::Srgb,
+        width: 2,
+        height: 2,
+        mips: vec![(0..16).collect(), vec![1, 2, 3, 4]]
+    }
+}
+
+fn mesh_nus() -> MeshRecord {
+    let vertex = |x: f32, joint: u32| VertexNUS {
+        position: [x, 1., 2.],
+        normal: [0., 1., 0.],
+        uv: [0.25, 0.75],
+        joints: [joint, 0, 0, 0],
+        weights: [1., 0., 0., 0.]
+    };
+    let mut tpose = [[0.;4];4];
+    for (i, row) in tpose.iter_mut().enumerate() { row[i] = 1. }
+    let bounds = Bounds::from_points([[0., 1., 2.], [1., 1., 2.], [2., 1., 2.]].into_iter());
+    MeshRecord {
+        name: "character".to_string(),
+        vertices: Vertices::NUS(vec![vertex(0., 0), vertex(1., 1), vertex(2., 1)].into()),
+        indices: Indices::new(vec![0, 1, 2, 2, 1, 0], 3),
+        sub_meshes: vec![
+            SubMeshRecord { material: "skin".to_string(), indices_start: 0, indices_len: 3, bounds },
+            SubMeshRecord { material: String::new(), indices_start: 3, indices_len: 3, bounds }
+        ],
+        bounds,
+        lods: vec![LodRecord { distance: 10., indices: vec![0..3, 3..3] }],
+        joints: vec![
+            JointRecord { name: "root".to_string(), parent: NO_JOINT, parents: Vec::new(), tpose, tpose_local: tpose, ibm: tpose, bounds: Bounds::EMPTY },
+            JointRecord { name: "arm".to_string(), parent: 0, parents: vec![0], tpose, tpose_local: tpose, ibm: tpose, bounds }
+        ]
+    }
+}
+
+/// Mesh of a single vertex drawn as one triangle, without sub-meshes, levels of detail or joints
+fn mesh(name: &str, vertices: Vertices) -> MeshRecord {
+    MeshRecord {
+        name: name.to_string(),
+        indices: Indices::new(vec![0, 0, 0], vertices.len()),
+        vertices,
+        sub_meshes: Vec::new(),
+        bounds: Bounds::EMPTY,
+        lods: Vec::new(),
+        joints: Vec::new()
+    }
+}
+
+fn animation() -> AnimationRecord {
+    let pose = |t: f32| JointPose { translation: [t, 0., 0.], rotation: [0., t, 0.], scale: [1., 1., 1.] };
+    AnimationRecord { name: "walk".to_string(), joints_length: 2, frames: vec![vec![pose(0.), pose(1.)], vec![pose(2.), pose(3.)]] }
+}
+
+fn material() -> MaterialRecord {
+    MaterialRecord {
+        name: "skin".to_string(),
+        shader: VertexType::NUS,
+        base_color_factor: [1., 0.5, 0.25, 1.],
+        base_color_texture: Some("ch_diffuse".to_string()),
+        normal_texture: None,
+        metallic_roughness_texture: Some("ch_orm".to_string()),
+        alpha_mode: AlphaMode::Mask(0.5),
+        double_sided: true
+    }
+}
+
+fn scene() -> SceneRecord {
+    let node = |name: &str, mesh: Option<&str>, children: Vec<u16>| SceneNode {
+        name: name.to_string(),
+        translation: [1., 2., 3.],
+        rotation: [0., FRAC_1_SQRT_2, 0., FRAC_1_SQRT_2],
+        scale: [1., 2., 1.],
+        mesh: mesh.map(str::to_string),
+        extras: String::new(),
+        children
+    };
+    SceneRecord {
+        name: "level_01".to_string(),
+        nodes: vec![
+            node("house", Some("level_01_house"), vec![1, 2]),
+            SceneNode { extras: r##"{"spawn":"#player","team":2}"##.to_string(), ..node("door", None, Vec::new()) },
+            node("chimney", Some("level_01_chimney"), Vec::new()),
+            node("tree", Some("level_01_tree"), Vec::new())
+        ],
+        roots: vec![0, 3]
+    }
+}
+
+fn round_trip(record: Record) {
+    let mut reader = Reader::from_bytes(record.encode());
+    assert_eq!(Record::decode(&mut reader).unwrap(), record);
+}
+
+#[test]
+fn texture_round_trip() {
+    round_trip(Record::Texture(texture()));
+    round_trip(Record::Texture(TextureRecord {
+        name: "rock".to_string(),
+        format: TextureFormat::Bc1,
+        channels: 3,
+        color_space: ColorSpace::Linear,
+        width: 8,
+        height: 4,
+        mips: vec![vec![7; 16], vec![8; 8], vec![9; 8], vec![10; 8]]
+    }));
+}
+
+#[test]
+fn too_many_mip_levels() {
+    let mut texture = texture();
+    texture.mips.push(vec![1, 2, 3, 4]);
+    let mut reader = Reader::from_bytes(texture.encode());
+    assert!(matches!(Record::decode(&mut reader), Err(AssetError::InvalidMipLevels { levels: 3, .. })));
+}
+
+#[test]
+fn texture_channels() {
+    round_trip(Record::Texture(TextureRecord {
+        name: "height".to_string(),
+        format: TextureFormat::Raw,
+        channels: 1,
+        color_space: ColorSpace::Linear,
+        width: 2,
+        height: 1,
+        mips: vec![vec![10, 20], vec![15]]
+    }));
+    let texture = TextureRecord { channels: 2, mips: vec![(0..8).collect(), vec![1, 2]], ..texture() };
+    let mut reader = Reader::from_bytes(texture.encode());
+    assert!(matches!(Record::decode(&mut reader), Err(AssetError::InvalidChannels { channels: 2, .. })));
+}
+
+#[test]
+fn bc1_round_trip() {
+    // two flat colors split in the middle of every block survive compression exactly
+    let (width, height) = (8, 8);
+    let mut rgba = Vec::new();
+    for _ in 0..height {
+        for x in 0..width {
+            rgba.extend_from_slice(if x % 4 < 2 { &[255, 0, 0, 255] } else { &[0, 0, 255, 255] });
+        }
+    }
+    let blocks = encode_bc1(&rgba, width, height);
+    assert_eq!(blocks.len(), TextureFormat::Bc1.level_len(width, height, 3));
+    assert_eq!(decode_bc1(&blocks, width, height), rgba);
+    assert_eq!(mip_levels(width, height), 4);
+    assert_eq!(mip_levels(1, 1), 1);
+}
+
+#[test]
+fn bc3_round_trip() {
+    // two flat colors with their own alpha, and a border block, survive compression exactly
+    let (width, height) = (6, 4);
+    let mut rgba = Vec::new();
+    for _ in 0..height {
+        for x in 0..width {
+            rgba.extend_from_slice(if x % 4 < 2 { &[255, 0, 0, 255] } else { &[0, 0, 255, 0] });
+        }
+    }
+    let blocks = encode_bc3(&rgba, width, height);
+    assert_eq!(blocks.len(), TextureFormat::Bc3.level_len(width, height, 4));
+    assert_eq!(decode_bc3(&blocks, width, height), rgba);
+}
+
+#[test]
+fn mesh_round_trip() {
+    round_trip(Record::Mesh(mesh_nus()));
+    round_trip(Record::Mesh(MeshRecord {
+        indices: Indices::U32(vec![0, 0, 0].into()),
+        sub_meshes: vec![SubMeshRecord { material: "stone".to_string(), indices_start: 0, indices_len: 3, bounds: Bounds::EMPTY }],
+        bounds: Bounds::from_points(std::iter::once([1., 2., 3.])),
+        ..mesh("cube", Vertices::Basic(vec![VertexBasic { position: [1., 2., 3.] }].into()))
+    }));
+    round_trip(Record::Mesh(mesh("quad", Vertices::U(vec![VertexU { position: [1., 2., 3.], uv: [0., 1.] }].into()))));
+    round_trip(Record::Mesh(mesh("tree", Vertices::NU(vec![VertexNU { position: [1., 2., 3.], normal: [0., 0., 1.], uv: [1., 0.] }].into()))));
+}
+
+#[test]
+fn index_size() {
+    assert_eq!(Indices::new(vec![0, 65535], 65536), Indices::U16(vec![0, 65535].into()));
+    assert_eq!(Indices::new(vec![0, 65536], 65537), Indices::U32(vec![0, 65536].into()));
+}
+
+#[test]
+fn sub_mesh_out_of_range() {
+    let mut mesh = mesh_nus();
+    mesh.sub_meshes[1].indices_len = 4;
+    let mut reader = Reader::from_bytes(mesh.encode());
+    assert!(matches!(Record::decode(&mut reader), Err(AssetError::SubMeshOutOfRange { .. })));
+    let mut mesh = mesh_nus();
+    mesh.lods[0].indices[1] = 3..7;
+    let mut reader = Reader::from_bytes(mesh.encode());
+    assert!(matches!(Record::decode(&mut reader), Err(AssetError::SubMeshOutOfRange { .. })));
+}
+
+#[test]
+fn animation_round_trip() {
+    round_trip(Record::Animation(animation()));
+}
+
+#[test]
+fn material_round_trip() {
+    round_trip(Record::Material(material()));
+    round_trip(Record::Material(MaterialRecord { alpha_mode: AlphaMode::Blend, base_color_texture: None, ..material() }));
+}
+
+#[test]
+fn scene_round_trip() {
+    round_trip(Record::Scene(scene()));
+    // a child before its parent could make a loop
+    let mut looped = scene();
+    looped.nodes[2].children.push(0);
+    let mut reader = Reader::from_bytes(looped.encode());
+    assert!(matches!(Record::decode(&mut reader), Err(AssetError::NodeOutOfRange { .. })));
+    let mut missing = scene();
+    missing.roots.push(4);
+    let mut reader = Reader::from_bytes(missing.encode());
+    assert!(matches!(Record::decode(&mut reader), Err(AssetError::NodeOutOfRange { .. })));
+}
+
+#[test]
+fn pack_round_trip() {
+    let records = [Record::Texture(texture()), Record::Mesh(mesh_nus()), Record::Animation(animation()), Record::Material(material()), Record::Scene(scene())];
+    let bytes = Writer::pack(&records.iter().map(Record::encode).collect::<Vec<_>>());
+    let mut reader = Reader::from_bytes(bytes);
+    let toc = reader.read_toc("test.bin").unwrap();
+    assert_eq!(toc.len(), records.len());
+    for (entry, record) in toc.iter().zip(records.iter()) {
+        assert_eq!(entry.kind, record.kind());
+        assert_eq!(entry.name, record.name());
+        assert_eq!(&reader.read_record(entry).unwrap(), record);
+    }
+}
+
+#[test]
+fn truncated_record() {
+    let bytes = Record::Animation(animation()).encode();
+    let mut reader = Reader::from_bytes(bytes[..bytes.len() - 10].to_vec());
+    assert!(matches!(Record::decode(&mut reader), Err(AssetError::UnexpectedEof { .. })));
+}
+
+#[test]
+fn unsupported_version() {
+    let mut bytes = Writer::pack(&[texture().encode()]);
+    bytes[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&(FORMAT_VERSION + 1).to_be_bytes());
+    let mut reader = Reader::from_bytes(bytes);
+    assert!(matches!(reader.read_toc("test.bin"), Err(AssetError::UnsupportedVersion { version, .. }) if version == FORMAT_VERSION + 1));
+}
+
+#[test]
+fn not_a_pack() {
+    let mut reader = Reader::from_bytes(b"not a pack at all".to_vec());
+    assert!(matches!(reader.read_toc("test.bin"), Err(AssetError::NotAPack { .. })));
+}
+
+/// compiler/compile.py writes animation records itself, inside blender, this is the layout it writes
+#[test]
+fn blender_animation_layout() {
+    let record = animation();
+    let mut bytes = vec![b'A'];
+    bytes.extend_from_slice(record.name.as_bytes());
+    bytes.push(b'#');
+    bytes.push(0);
+    bytes.extend_from_slice(&record.joints_length.to_be_bytes());
+    bytes.extend_from_slice(&(record.frames.len() as u32).to_be_bytes());
+    for pose in record.frames.iter().flatten() {
+        for v in pose.translation.iter().chain(pose.rotation.iter()).chain(pose.scale.iter()) {
+            bytes.extend_from_slice(&v.to_be_bytes());
+        }
+    }
+    bytes.extend_from_slice(b"END");
+    assert_eq!(bytes, record.encode());
+    assert_eq!(Record::decode(&mut Reader::from_bytes(bytes)).unwrap(), Record::Animation(record));
+}
+
+#[test]
+fn compressed_round_trip() {
+    let records = [Record::Texture(texture()), Record::Mesh(mesh_nus()), Record::Animation(animation())];
+    for compression in [Compression::Zstd, Compression::Lz4] {
+        let encoded: Vec<Vec<u8>> = records.iter().map(|record| compress(&record.encode(), compression)).collect();
+        for (bytes, record) in encoded.iter().zip(records.iter()) {
+            assert_eq!(pack::compression(bytes), Some((compression, record.encode().len() - record.name().len() - 3)));
+        }
+        let mut reader = Reader::from_bytes(Writer::pack(&encoded));
+        let toc = reader.read_toc("test.bin").unwrap();
+        for (entry, record) in toc.iter().zip(records.iter()) {
+            assert_eq!(entry.name, record.name());
+            assert_eq!(&reader.read_record(entry).unwrap(), record);
+        }
+    }
+    let mut bytes = compress(&animation().encode(), Compression::Lz4);
+    let last = bytes.len() - 1;
+    bytes[last] ^= 0xff;
+    assert!(matches!(Record::decode(&mut Reader::from_bytes(bytes)), Err(AssetError::Decompression { .. } | AssetError::BadEndMarker { .. })));
+}
+
+#[test]
+fn compressed_length_is_checked_before_allocating() {
+    for compression in [Compression::Zstd, Compression::Lz4] {
+        let mut bytes = compress(&animation().encode(), compression);
+        let (_, len) = pack::compression(&bytes).unwrap();
+        // the decompressed length follows the name and the compression id
+        let at = animation().name.len() + 3;
+        assert_eq!(&bytes[at..at + 4], &(len as u32).to_be_bytes());
+        bytes[at..at + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+        assert!(matches!(Record::decode(&mut Reader::from_bytes(bytes)), Err(AssetError::Decompression { error, .. }) if error.contains("claims")));
+    }
+}
+
+#[test]
+fn mapped_mesh_is_not_copied() {
+    let records = [texture().encode(), mesh_nus().encode(), compress(&mesh_nus().encode(), Compression::Lz4)];
+    let path = std::env::temp_dir().join(format!("pack_test_{}.bin", std::process::id()));
+    std::fs::write(&path, Writer::pack(&records)).unwrap();
+    let mut reader = unsafe { Reader::map(&path) }.unwrap();
+    let toc = reader.read_toc(&path).unwrap();
+    for entry in toc.iter().skip(1) {
+        assert_eq!(entry.offset % RECORD_ALIGNMENT, 0);
+        match reader.clone().read_record(entry).unwrap() {
+            Record::Mesh(mesh) => {
+                assert!(matches!(mesh.vertices, Vertices::NUS(Slice::Shared(..))));
+                assert!(matches!(mesh.indices, Indices::U16(Slice::Shared(..))));
+                assert_eq!(mesh, mesh_nus());
+            },
+            record => panic!("expected a mesh, found {:?}", record.name())
+        }
+    }
+    drop(reader);
+    std::fs::remove_file(path).ok();
+}
+
+#[test]
+fn quantized_round_trip() {
+    let mut character = mesh_nus();
+    character.vertices = character.vertices.quantize().unwrap();
+    assert_eq!(character.vertices.vertex_type(), VertexType::NUSQ);
+    round_trip(Record::Mesh(character));
+    let tree = Vertices::NU(vec![VertexNU { position: [1., 2., 3.], normal: [0., 0., -1.], uv: [1., 0.] }].into());
+    let quantized = tree.quantize().unwrap();
+    assert_eq!(quantized.vertex_type(), VertexType::NUQ);
+    round_trip(Record::Mesh(mesh("tree", quantized)));
+    // uvs outside of 0 to 1 keep the mesh at full precision
+    let tiled = Vertices::NU(vec![VertexNU { position: [0.;3], normal: [0., 1., 0.], uv: [2., 0.] }].into());
+    assert_eq!(tiled.quantize(), None);
+}
+
+#[test]
+fn tangent_round_trip() {
+    let mut character = mesh_nus();
+    character.vertices = match character.vertices {
+        Vertices::NUS(vertices) => Vertices::NUST(vertices.iter().map(|v| VertexNUST {
+            position: v.position,
+            normal: v.normal,
+            uv: v.uv,
+            tangent: [1., 0., 0., -1.],
+            joints: v.joints,
+            weights: v.weights
+        }).collect()),
+        _ => unreachable!()
+    };
+    assert!(character.vertices.vertex_type().has_joints());
+    round_trip(Record::Mesh(character));
+    round_trip(Record::Mesh(mesh("rock", Vertices::NUT(vec![VertexNUT { position: [1., 2., 3.], normal: [0., 1., 0.], uv: [0.5, 0.5], tangent: [0., 0., 1., 1.] }].into()))));
+}
+
+#[test]
+fn octahedral_normals() {
+    for i in 0..1000 {
+        let (theta, phi) = (i as f32 * 0.618 * std::f32::consts::TAU, (i as f32 / 999. * 2. - 1.).acos());
+        let n = [phi.sin() * theta.cos(), phi.sin() * theta.sin(), phi.cos()];
+        let decoded = decode_octahedral(encode_octahedral(n));
+        let dot: f32 = (0..3).map(|c| n[c] * decoded[c]).sum();
+        assert!(dot > 0.99999, "{:?} decoded as {:?}", n, decoded);
+    }
+}
+
+#[test]
+fn bounds() {
+    let bounds = Bounds::from_points([[-1., 0., 0.], [1., 2., 0.], [0., 0., 4.]].into_iter());
+    assert_eq!((bounds.min, bounds.max, bounds.center), ([-1., 0., 0.], [1., 2., 4.], [0., 1., 2.]));
+    assert!((bounds.radius - 6f32.sqrt()).abs() < 1e-6);
+    assert!(Bounds::from_points(std::iter::empty()).is_empty());
+    assert_eq!(Bounds::EMPTY.union(&bounds), bounds);
+    let union = bounds.union(&Bounds::from_points(std::iter::once([5., 0., 0.])));
+    assert_eq!((union.min, union.max), ([-1., 0., 0.], [5., 2., 4.]));
+    let mut mat = [[0.;4];4];
+    for (i, column) in mat.iter_mut().enumerate() { column[i] = 2. }
+    mat[3] = [1., 0., 0., 1.];
+    let moved = bounds.transform(mat);
+    assert_eq!((moved.min, moved.max, moved.center), ([-1., 0., 0.], [3., 4., 8.], [1., 2., 4.]));
+    assert!((moved.radius - bounds.radius * 2.).abs() < 1e-5);
+}
+
+#[test]
+fn joints_past_255() {
+    let mut mesh = mesh_nus();
+    let joint = mesh.joints[1].clone();
+    mesh.joints.extend((2..300u16).map(|id| JointRecord { name: format!("bone{}", id), parent: id - 1, parents: (0..id).collect(), ..joint.clone() }));
+    round_trip(Record::Mesh(mesh));
+    let mut animation = animation();
+    animation.joints_length = 300;
+    animation.frames = vec![vec![animation.frames[0][0]; 300]];
+    round_trip(Record::Animation(animation));
+    // u8 joints of quantized vertices can not address them, unused influences do not matter
+    let vertex = |joints: [u32;4], weights: [f32;4]| VertexNUS { position: [0.;3], normal: [0., 1., 0.], uv: [0.;2], joints, weights };
+    assert_eq!(Vertices::NUS(vec![vertex([299, 0, 0, 0], [1., 0., 0., 0.])].into()).quantize(), None);
+    assert!(Vertices::NUS(vec![vertex([1, 299, 0, 0], [1., 0., 0., 0.])].into()).quantize().is_some());
+}